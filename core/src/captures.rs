@@ -0,0 +1,101 @@
+use std::{fmt, ops::Range, sync::Arc};
+
+use crate::{nfa::Nfa, pikevm, usize_range, Matches};
+
+/// The positions of the capture groups of a single match.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Captures {
+    slots: Vec<Option<u64>>,
+    names: Arc<[Option<String>]>,
+}
+
+/// An iterator over the (non-overlapping) matches, resolving their capture groups.
+#[derive(Debug)]
+pub struct CaptureMatches<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    nfa: &'r Nfa,
+    cache: pikevm::Cache,
+}
+
+impl Captures {
+    /// Captures of a match without resolving any groups but group 0.
+    pub(crate) fn from_match(mat: Range<u64>, names: &Arc<[Option<String>]>) -> Captures {
+        let mut slots = vec![None; names.len() * 2];
+        slots[0] = Some(mat.start);
+        slots[1] = Some(mat.end);
+
+        Captures {
+            slots,
+            names: names.clone(),
+        }
+    }
+
+    /// Returns the range matched by the capture group with the given index.
+    /// Group 0 is always the whole match.
+    /// Returns `None` if the group doesn't exist or didn't participate in the match.
+    pub fn get(&self, i: usize) -> Option<Range<usize>> {
+        let start = (*self.slots.get(i * 2)?)?;
+        let end = self.slots[i * 2 + 1]?;
+        Some(usize_range(start..end))
+    }
+
+    /// Returns the range matched by the capture group with the given name.
+    /// Returns `None` if the group doesn't exist or didn't participate in the match.
+    pub fn name(&self, name: &str) -> Option<Range<usize>> {
+        let i = self.names.iter().position(|n| n.as_deref() == Some(name))?;
+        self.get(i)
+    }
+}
+
+impl fmt::Debug for Captures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries((0..self.names.len()).map(|i| self.get(i)))
+            .finish()
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> CaptureMatches<'r, Haystack> {
+    pub(crate) fn new(
+        mut matches: Matches<'r, Haystack>,
+        nfa: &'r Nfa,
+    ) -> CaptureMatches<'r, Haystack> {
+        // The capture groups are found by running over the bytes of the match again.
+        matches.keep_match_bytes();
+
+        CaptureMatches {
+            matches,
+            nfa,
+            cache: pikevm::Cache::new(nfa),
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for CaptureMatches<'_, Haystack> {
+    type Item = Captures;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mat = self.matches.next_match()?;
+        Some(self.matches.captures(self.nfa, &mut self.cache, mat))
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Matches<'_, Haystack> {
+    /// Resolves the capture groups of the match just found.
+    pub(crate) fn captures(
+        &mut self,
+        nfa: &Nfa,
+        cache: &mut pikevm::Cache,
+        mat: Range<u64>,
+    ) -> Captures {
+        // The bytes of the match are still in the window of the search.
+        match pikevm::captures(nfa, cache, &mut self.input, mat.clone()) {
+            Some(slots) => Captures {
+                slots,
+                names: nfa.names().clone(),
+            },
+            // The NFA of a loaded regex might not match what its DFAs do, see [Regex::from_bytes].
+            None => Captures::from_match(mat, nfa.names()),
+        }
+    }
+}
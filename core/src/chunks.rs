@@ -0,0 +1,254 @@
+//! Searching haystacks which come in slices, e.g. from a reader.
+
+#[cfg(feature = "std")]
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+    ops::Range,
+};
+
+#[cfg(feature = "std")]
+use crate::Matches;
+
+/// The bytes of an iterator over chunks, see [Regex::matches_chunks](crate::Regex::matches_chunks).
+#[derive(Debug)]
+pub struct Chunks<I: Iterator> {
+    chunks: I,
+    chunk: Option<I::Item>,
+    /// The index of the next byte in `chunk`.
+    pos: usize,
+}
+
+/// The bytes of an iterator over chunks going backwards, last byte first,
+/// see [Regex::rmatches_tree](crate::Regex::rmatches_tree).
+#[derive(Debug)]
+pub struct RevChunks<I: Iterator> {
+    chunks: I,
+    chunk: Option<I::Item>,
+    /// The number of bytes in `chunk` which weren't returned yet.
+    left: usize,
+}
+
+/// An iterator over the matches in a reader, see [Regex::matches_reader](crate::Regex::matches_reader).
+///
+/// Reading fails at most once, after which the iterator ends.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ReaderMatches<'r, R: BufRead> {
+    matches: Matches<'r, ReaderBytes<R>>,
+    done: bool,
+}
+
+/// The bytes of a reader, taken straight from its buffer.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub(crate) struct ReaderBytes<R> {
+    reader: R,
+    /// How many bytes are left in the buffer of the reader.
+    buffered: usize,
+    /// The error which ended the bytes early.
+    error: Option<io::Error>,
+}
+
+impl<I: Iterator> Chunks<I> {
+    pub(crate) fn new(chunks: I) -> Chunks<I> {
+        Chunks {
+            chunks,
+            chunk: None,
+            pos: 0,
+        }
+    }
+}
+
+impl<I: Iterator> Chunks<I>
+where
+    I::Item: AsRef<[u8]>,
+{
+    /// Copies the rest of the current chunk, or of the next one if it's used up, see [Input::set_fill].
+    ///
+    /// [Input::set_fill]: crate::input::Input::set_fill
+    #[cfg(feature = "std")]
+    pub(crate) fn fill(&mut self, lookahead: &mut VecDeque<u8>, max: usize) {
+        loop {
+            if let Some(chunk) = &self.chunk {
+                let rest = &chunk.as_ref()[self.pos..];
+                if !rest.is_empty() {
+                    let len = rest.len().min(max);
+                    lookahead.extend(&rest[..len]);
+                    self.pos += len;
+                    return;
+                }
+            }
+
+            match self.chunks.next() {
+                Some(chunk) => self.chunk = Some(chunk),
+                None => return,
+            }
+            self.pos = 0;
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Chunks<I>
+where
+    I::Item: AsRef<[u8]>,
+{
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(chunk) = &self.chunk {
+                if let Some(&b) = chunk.as_ref().get(self.pos) {
+                    self.pos += 1;
+                    return Some(b);
+                }
+            }
+
+            self.chunk = Some(self.chunks.next()?);
+            self.pos = 0;
+        }
+    }
+
+    /// At least the rest of the current chunk is there.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self
+            .chunk
+            .as_ref()
+            .map_or(0, |chunk| chunk.as_ref().len() - self.pos);
+        (left, None)
+    }
+}
+
+impl<I: Iterator> RevChunks<I> {
+    pub(crate) fn new(chunks: I) -> RevChunks<I> {
+        RevChunks {
+            chunks,
+            chunk: None,
+            left: 0,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for RevChunks<I>
+where
+    I::Item: AsRef<[u8]>,
+{
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(chunk) = &self.chunk {
+                if self.left > 0 {
+                    self.left -= 1;
+                    return Some(chunk.as_ref()[self.left]);
+                }
+            }
+
+            let chunk = self.chunks.next()?;
+            self.left = chunk.as_ref().len();
+            self.chunk = Some(chunk);
+        }
+    }
+
+    /// At least the rest of the current chunk is there.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.left, None)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> ReaderBytes<R> {
+    pub(crate) fn new(reader: R) -> ReaderBytes<R> {
+        ReaderBytes {
+            reader,
+            buffered: 0,
+            error: None,
+        }
+    }
+
+    /// Copies the buffer of the reader, consuming it all at once, see [Input::set_fill].
+    ///
+    /// [Input::set_fill]: crate::input::Input::set_fill
+    pub(crate) fn fill(&mut self, lookahead: &mut VecDeque<u8>, max: usize) {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(bytes) => {
+                    let len = bytes.len().min(max);
+                    lookahead.extend(&bytes[..len]);
+                    self.buffered = bytes.len() - len;
+                    self.reader.consume(len);
+                    return;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.error = Some(err);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> Iterator for ReaderBytes<R> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok([]) => return None,
+                Ok(bytes) => {
+                    let b = bytes[0];
+                    self.buffered = bytes.len() - 1;
+                    self.reader.consume(1);
+                    return Some(b);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.error = Some(err);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Only the buffered bytes are there without reading again.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buffered, None)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'r, R: BufRead> ReaderMatches<'r, R> {
+    pub(crate) fn new(matches: Matches<'r, ReaderBytes<R>>) -> ReaderMatches<'r, R> {
+        ReaderMatches {
+            matches,
+            done: false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> Iterator for ReaderMatches<'_, R> {
+    type Item = io::Result<Range<u64>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mat = self.matches.next_match();
+
+        // A match found when reading failed might have been cut short, so drop it.
+        if let Some(err) = self.matches.input.haystack_mut().error.take() {
+            self.done = true;
+            return Some(Err(err));
+        }
+
+        self.done = mat.is_none();
+        mat.map(Ok)
+    }
+}
@@ -0,0 +1,186 @@
+//! Searching in both directions from a position in the haystack, like an editor does.
+
+use core::ops::Range;
+
+use crate::{Matches, Regex};
+
+/// A haystack which can be walked in both directions from a position, see [Regex::find_next].
+pub trait Cursor {
+    /// Returns the byte after the cursor and moves past it.
+    fn next_byte(&mut self) -> Option<u8>;
+
+    /// Returns the byte before the cursor and moves before it.
+    fn prev_byte(&mut self) -> Option<u8>;
+
+    /// The position of the cursor, in bytes from the start of the haystack.
+    fn position(&self) -> u64;
+
+    /// The length of the haystack.
+    fn len(&self) -> u64;
+
+    /// Returns whether the haystack is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Moves the cursor to `position`, which is at most the length of the haystack.
+    fn seek(&mut self, position: u64);
+}
+
+/// A [Cursor] over a slice.
+#[derive(Debug, Clone)]
+pub struct SliceCursor<'h> {
+    bytes: &'h [u8],
+    position: usize,
+}
+
+impl<'h> SliceCursor<'h> {
+    /// Creates a cursor over `bytes` at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is past the end of `bytes`.
+    pub fn new(bytes: &'h [u8], position: usize) -> SliceCursor<'h> {
+        assert!(position <= bytes.len(), "cursor out of bounds");
+        SliceCursor { bytes, position }
+    }
+}
+
+impl Cursor for SliceCursor<'_> {
+    fn next_byte(&mut self) -> Option<u8> {
+        let b = *self.bytes.get(self.position)?;
+        self.position += 1;
+        Some(b)
+    }
+
+    fn prev_byte(&mut self) -> Option<u8> {
+        self.position = self.position.checked_sub(1)?;
+        Some(self.bytes[self.position])
+    }
+
+    fn position(&self) -> u64 {
+        self.position as u64
+    }
+
+    fn len(&self) -> u64 {
+        self.bytes.len() as u64
+    }
+
+    fn seek(&mut self, position: u64) {
+        assert!(position <= self.len(), "cursor out of bounds");
+        self.position = position as usize;
+    }
+}
+
+/// The bytes after a cursor.
+struct Forward<'c, C>(&'c mut C);
+
+/// The bytes before a cursor, going backwards.
+struct Backward<'c, C>(&'c mut C);
+
+impl<C: Cursor> Iterator for Forward<'_, C> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_byte()
+    }
+}
+
+impl<C: Cursor> Iterator for Backward<'_, C> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.prev_byte()
+    }
+}
+
+/// Returns the bytes next to the cursor which look-around assertions can look at, nearest last,
+/// walking `away` from the cursor and `back` to it.
+#[cfg(feature = "std")]
+fn context<C: Cursor>(
+    cursor: &mut C,
+    away: fn(&mut C) -> Option<u8>,
+    back: fn(&mut C) -> Option<u8>,
+) -> Vec<u8> {
+    let mut bytes = (0..4).map_while(|_| away(cursor)).collect::<Vec<_>>();
+    for _ in &bytes {
+        back(cursor);
+    }
+    bytes.reverse();
+    bytes
+}
+
+/// Searches for the matches starting at or after the cursor's position.
+fn forward<'r, 'c, C: Cursor>(regex: &'r Regex, cursor: &'c mut C) -> Matches<'r, Forward<'c, C>> {
+    let base = cursor.position();
+    #[cfg(feature = "std")]
+    let context = context(cursor, C::prev_byte, C::next_byte);
+
+    let matches = Matches::new(regex, false, Forward(cursor), base);
+    #[cfg(feature = "std")]
+    let matches = {
+        let mut matches = matches;
+        matches.input.set_context(context);
+        matches
+    };
+    matches
+}
+
+/// Finds the start of the nearest match which fits before the cursor's position, searching backwards.
+fn backward_start<C: Cursor>(regex: &Regex, cursor: &mut C) -> Option<u64> {
+    // Offsets of the backwards search count from the end of the haystack.
+    let len = cursor.len();
+    let base = len - cursor.position();
+    #[cfg(feature = "std")]
+    let context = context(cursor, C::next_byte, C::prev_byte);
+
+    let mut matches = Matches::new(regex, true, Backward(cursor), base);
+    #[cfg(feature = "std")]
+    matches.input.set_context(context);
+    // An empty match at the position doesn't start before it.
+    let mat = core::iter::from_fn(|| matches.next_match()).find(|mat| mat.end > base)?;
+    Some(len - mat.end)
+}
+
+/// Finds the last match starting before `position`.
+fn backward<C: Cursor>(regex: &Regex, cursor: &mut C, position: u64) -> Option<Range<u64>> {
+    // Matches which don't fit before the position are found by searching forwards,
+    // from the nearest match which does or else from the start of the haystack.
+    cursor.seek(position);
+    let start = backward_start(regex, cursor).unwrap_or(0);
+    cursor.seek(start);
+
+    let mut matches = forward(regex, cursor);
+    core::iter::from_fn(|| matches.next_match())
+        .take_while(|mat| mat.start < position)
+        .last()
+}
+
+/// Finds the first match starting at or after the cursor, see [Regex::find_next].
+pub(crate) fn find_next(regex: &Regex, cursor: &mut impl Cursor, wrap: bool) -> Option<Range<u64>> {
+    let position = cursor.position();
+
+    let mut mat = forward(regex, cursor).next_match();
+    if mat.is_none() && wrap {
+        cursor.seek(0);
+        mat = forward(regex, cursor).next_match();
+    }
+
+    cursor.seek(position);
+    mat
+}
+
+/// Finds the nearest match starting before the cursor, see [Regex::find_prev].
+pub(crate) fn find_prev(regex: &Regex, cursor: &mut impl Cursor, wrap: bool) -> Option<Range<u64>> {
+    let position = cursor.position();
+
+    let mut mat = backward(regex, cursor, position);
+    if mat.is_none() && wrap {
+        mat = backward(regex, cursor, cursor.len());
+    }
+
+    cursor.seek(position);
+    mat
+}
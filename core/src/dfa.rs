@@ -0,0 +1,620 @@
+//! Searching with DFAs, which are either built upfront or determinized lazily.
+//!
+//! The end of a match is found first, then its start by running a reverse DFA
+//! over the bytes of the window backwards.
+
+use core::ops::Range;
+
+use regex_automata::{DenseDFA, SparseDFA, StateID, DFA};
+
+use crate::{
+    input::{Input, Read},
+    MatchKind, Progress, Step,
+};
+#[cfg(feature = "std")]
+use crate::{
+    lazy::{self, Lazy},
+    nfa::Nfa,
+    pikevm, Dfa, StateIdWidth,
+};
+
+/// A deterministic automaton, stepped one byte at a time.
+pub(crate) trait Automaton {
+    type State: Copy + Eq;
+
+    fn start_state(&mut self) -> Self::State;
+
+    /// Returns the state reached from `state` on `b`.
+    fn next_state(&mut self, state: Self::State, b: u8) -> Result<Self::State, GaveUp>;
+
+    fn is_match_state(&self, state: Self::State) -> bool;
+
+    fn is_dead_state(&self, state: Self::State) -> bool;
+
+    /// Returns whether every byte leads from `state` to the dead state,
+    /// so a search is over without looking at the next byte.
+    /// Lazy DFAs can't step through all bytes here, since that may clear their cache.
+    fn is_final(&mut self, state: Self::State) -> Result<bool, GaveUp> {
+        for b in 0..=255 {
+            let next = self.next_state(state, b)?;
+            if !self.is_dead_state(next) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The earliest pattern matching in a match state.
+    /// Only lazy DFAs match several patterns, the DFAs of a regex always match pattern 0.
+    fn pattern(&self, _state: Self::State) -> usize {
+        0
+    }
+
+    /// Converts the state to an index, to keep it while a search waits for more bytes.
+    fn to_index(state: Self::State) -> usize;
+
+    fn from_index(index: usize) -> Self::State;
+}
+
+/// A DFA with narrower state IDs or sparse transitions than [Dfa].
+/// All DFAs of a regex use the same representation.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub(crate) enum CompactDfa {
+    Dense8(DenseDFA<Vec<u8>, u8>),
+    Dense16(DenseDFA<Vec<u16>, u16>),
+    Dense32(DenseDFA<Vec<u32>, u32>),
+    Sparse8(SparseDFA<Vec<u8>, u8>),
+    Sparse16(SparseDFA<Vec<u8>, u16>),
+    Sparse32(SparseDFA<Vec<u8>, u32>),
+    Sparse(SparseDFA<Vec<u8>, usize>),
+}
+
+/// Evaluates `$body` with `$inner` bound to the DFA inside a [CompactDfa].
+#[cfg(feature = "std")]
+macro_rules! with_compact_dfa {
+    ($dfa:expr, $inner:ident => $body:expr) => {
+        match $dfa {
+            CompactDfa::Dense8($inner) => $body,
+            CompactDfa::Dense16($inner) => $body,
+            CompactDfa::Dense32($inner) => $body,
+            CompactDfa::Sparse8($inner) => $body,
+            CompactDfa::Sparse16($inner) => $body,
+            CompactDfa::Sparse32($inner) => $body,
+            CompactDfa::Sparse($inner) => $body,
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+pub(crate) use with_compact_dfa;
+
+/// How far the window may grow without a match before checking which bytes can be dropped.
+const SCAN_WINDOW: u64 = 256;
+
+/// A lazy DFA matching what precedes a position in a match, read backwards from there.
+/// Running it backwards over the window finds the earliest byte a match could still start at.
+#[cfg(feature = "std")]
+pub(crate) struct Live<'a> {
+    nfa: &'a Nfa,
+    /// The cache is only created once the window grew long enough to be checked.
+    cache: &'a mut Option<lazy::Cache>,
+    cache_size: usize,
+}
+
+/// Without `std`, the window drops its oldest bytes by itself instead.
+#[cfg(not(feature = "std"))]
+pub(crate) type Live<'a> = &'a core::convert::Infallible;
+
+/// A lazy DFA stopped searching, because its cache had to be cleared too often.
+#[derive(Debug)]
+pub(crate) struct GaveUp;
+
+/// Why a search stopped without a match.
+pub(crate) enum Stop {
+    Step(Step),
+    GaveUp,
+    /// The bytes of a partial input ran out, see [Input::set_partial].
+    Pending(Progress),
+    /// The match got longer than the input keeps, see [Input::set_max_len] and [Input::set_max_window].
+    /// Only the Pike VM can go on without looking at all of its bytes again.
+    #[cfg(feature = "std")]
+    TooLong,
+    /// The bytes a match might start in were dropped to make room in the window,
+    /// so where it starts isn't known.
+    #[cfg(not(feature = "std"))]
+    Truncated,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Live<'a> {
+    pub(crate) fn new(
+        nfa: &'a Nfa,
+        cache: &'a mut Option<lazy::Cache>,
+        cache_size: usize,
+    ) -> Live<'a> {
+        Live {
+            nfa,
+            cache,
+            cache_size,
+        }
+    }
+
+    fn reborrow(&mut self) -> Live<'_> {
+        Live::new(self.nfa, self.cache, self.cache_size)
+    }
+
+    /// Returns the earliest index in the window where a match could still start,
+    /// or `None` if the lazy DFA gave up.
+    fn start<Haystack: Iterator<Item = u8>>(&mut self, input: &Input<Haystack>) -> Option<u64> {
+        let (nfa, cache_size) = (self.nfa, self.cache_size);
+        let cache = self
+            .cache
+            .get_or_insert_with(|| lazy::Cache::new(nfa, true, cache_size));
+        let mut dfa = Lazy::new(nfa, cache);
+
+        let mut state = dfa.start_state();
+        let mut start = input.position();
+
+        for (i, b) in input.window().rev() {
+            state = dfa.next_state(state, b).ok()?;
+
+            if dfa.is_match_state(state) {
+                start = i;
+            } else if dfa.is_dead_state(state) {
+                break;
+            }
+        }
+
+        Some(start)
+    }
+}
+
+#[cfg(feature = "std")]
+impl CompactDfa {
+    /// Converts `dfa`, failing if its states don't fit into state IDs of the given width.
+    pub(crate) fn new(
+        dfa: &Dfa,
+        sparse: bool,
+        width: StateIdWidth,
+    ) -> Result<CompactDfa, regex_automata::Error> {
+        Ok(match (sparse, width) {
+            (false, StateIdWidth::U8) => CompactDfa::Dense8(dfa.to_sized()?),
+            (false, StateIdWidth::U16) => CompactDfa::Dense16(dfa.to_sized()?),
+            (false, StateIdWidth::U32) => CompactDfa::Dense32(dfa.to_sized()?),
+            (false, StateIdWidth::Usize) => unreachable!("dense DFAs with `usize` aren't compact"),
+            (true, StateIdWidth::U8) => CompactDfa::Sparse8(dfa.to_sparse_sized()?),
+            (true, StateIdWidth::U16) => CompactDfa::Sparse16(dfa.to_sparse_sized()?),
+            (true, StateIdWidth::U32) => CompactDfa::Sparse32(dfa.to_sparse_sized()?),
+            (true, StateIdWidth::Usize) => CompactDfa::Sparse(dfa.to_sparse()?),
+        })
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        with_compact_dfa!(self, dfa => dfa.memory_usage())
+    }
+}
+
+impl<T: AsRef<[S]>, S: StateID> Automaton for &DenseDFA<T, S> {
+    type State = S;
+
+    fn start_state(&mut self) -> S {
+        DFA::start_state(*self)
+    }
+
+    fn next_state(&mut self, state: S, b: u8) -> Result<S, GaveUp> {
+        Ok(unsafe { self.next_state_unchecked(state, b) })
+    }
+
+    fn is_match_state(&self, state: S) -> bool {
+        DFA::is_match_state(*self, state)
+    }
+
+    fn is_dead_state(&self, state: S) -> bool {
+        DFA::is_dead_state(*self, state)
+    }
+
+    fn to_index(state: S) -> usize {
+        state.to_usize()
+    }
+
+    fn from_index(index: usize) -> S {
+        S::from_usize(index)
+    }
+}
+
+impl<T: AsRef<[u8]>, S: StateID> Automaton for &SparseDFA<T, S> {
+    type State = S;
+
+    fn start_state(&mut self) -> S {
+        DFA::start_state(*self)
+    }
+
+    fn next_state(&mut self, state: S, b: u8) -> Result<S, GaveUp> {
+        Ok(DFA::next_state(*self, state, b))
+    }
+
+    fn is_match_state(&self, state: S) -> bool {
+        DFA::is_match_state(*self, state)
+    }
+
+    fn is_dead_state(&self, state: S) -> bool {
+        DFA::is_dead_state(*self, state)
+    }
+
+    fn to_index(state: S) -> usize {
+        state.to_usize()
+    }
+
+    fn from_index(index: usize) -> S {
+        S::from_usize(index)
+    }
+}
+
+impl From<Step> for Stop {
+    fn from(step: Step) -> Self {
+        Stop::Step(step)
+    }
+}
+
+impl From<GaveUp> for Stop {
+    fn from(_: GaveUp) -> Self {
+        Stop::GaveUp
+    }
+}
+
+/// Searches for the next match, finding its end before its start.
+/// `unanchored` and `anchored` run in the direction of the search, `rev` in the opposite one.
+/// The search goes on from `progress` if it had to wait for more bytes before.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn search<Haystack: Iterator<Item = u8>>(
+    input: &mut Input<Haystack>,
+    mut unanchored: impl Automaton,
+    mut anchored: impl Automaton,
+    mut rev: impl Automaton,
+    live: Option<Live<'_>>,
+    kind: MatchKind,
+    stop_when_skipped: bool,
+    progress: Option<Progress>,
+) -> Result<Range<u64>, Stop> {
+    let (search_start, start, end, state) = match progress {
+        Some(Progress::Extend {
+            search_start,
+            state,
+            start,
+            end,
+        }) => (search_start, start, end, Some(state)),
+        progress => {
+            let (search_start, end) =
+                find_end(input, &mut unanchored, live, stop_when_skipped, progress)?;
+            let (start, _) = find_start(input, &mut rev, search_start, end)?;
+            (search_start, start, end, None)
+        }
+    };
+
+    if kind == MatchKind::LeftmostLongest {
+        let end = extend_end(input, &mut anchored, search_start, start, end, state)?;
+        return Ok(start..end);
+    }
+
+    Ok(start..end)
+}
+
+/// Like [search], for the DFAs of a regex with compact DFAs.
+#[cfg(feature = "std")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn search_compact<Haystack: Iterator<Item = u8>>(
+    input: &mut Input<Haystack>,
+    unanchored: &CompactDfa,
+    anchored: &CompactDfa,
+    rev: &CompactDfa,
+    live: Option<Live<'_>>,
+    kind: MatchKind,
+    stop_when_skipped: bool,
+    progress: Option<Progress>,
+) -> Result<Range<u64>, Stop> {
+    macro_rules! dispatch {
+        ($($variant:ident),*) => {
+            match (unanchored, anchored, rev) {
+                $(
+                    (
+                        CompactDfa::$variant(unanchored),
+                        CompactDfa::$variant(anchored),
+                        CompactDfa::$variant(rev),
+                    ) => search(
+                        input,
+                        unanchored,
+                        anchored,
+                        rev,
+                        live,
+                        kind,
+                        stop_when_skipped,
+                        progress,
+                    ),
+                )*
+                _ => unreachable!("all DFAs of a regex have the same representation"),
+            }
+        };
+    }
+
+    dispatch!(Dense8, Dense16, Dense32, Sparse8, Sparse16, Sparse32, Sparse)
+}
+
+/// Searches for the next match of a regex with look-around, which only the Pike VM running `nfa` checks.
+/// `relaxed` finds the leftmost-first match of the regex without its assertions,
+/// and as every match of the regex is also one of those, none can start before it.
+/// The Pike VM then only runs until no match can start up to its end anymore,
+/// and the search goes on from there with `relaxed` again.
+#[cfg(feature = "std")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn search_look_around<Haystack: Iterator<Item = u8>>(
+    input: &mut Input<Haystack>,
+    mut relaxed: impl Automaton,
+    mut live: Option<Live<'_>>,
+    nfa: &Nfa,
+    cache: &mut pikevm::Cache,
+    kind: MatchKind,
+    stop_when_skipped: bool,
+    progress: Option<Progress>,
+) -> Result<Range<u64>, Stop> {
+    if let Some(Progress::Nfa { .. }) = progress {
+        return pikevm::find(nfa, cache, kind, input, stop_when_skipped, None, progress)
+            .map(|(_, mat)| mat);
+    }
+
+    let live_now = live.as_mut().map(Live::reborrow);
+    let (search_start, end) = find_end(input, &mut relaxed, live_now, stop_when_skipped, progress)?;
+
+    // The window holds all of the match without the assertions,
+    // so `live` finds a start no later than its own.
+    let start = live.and_then(|mut live| live.start(input));
+    input.unread(start.unwrap_or(input.window_start()).max(search_start));
+
+    pikevm::find(nfa, cache, kind, input, stop_when_skipped, Some(end), None).map(|(_, mat)| mat)
+}
+
+/// Finds the end of the leftmost-first match, consuming the haystack up to that point.
+/// Returns where the search started, which is kept in `progress` if it had to wait for more bytes.
+/// Bytes are dropped from the window once `live` shows that no match can start in them.
+#[cfg_attr(not(feature = "std"), allow(unused_variables, unused_mut))]
+pub(crate) fn find_end<Haystack: Iterator<Item = u8>, A: Automaton>(
+    input: &mut Input<Haystack>,
+    dfa: &mut A,
+    mut live: Option<Live<'_>>,
+    stop_when_skipped: bool,
+    progress: Option<Progress>,
+) -> Result<(u64, u64), Stop> {
+    let (search_start, mut state, mut end, mut limit) = match progress {
+        Some(Progress::End {
+            search_start,
+            state,
+            end,
+            limit,
+        }) => (search_start, A::from_index(state), end, limit),
+        _ => {
+            let state = dfa.start_state();
+            if dfa.is_dead_state(state) {
+                return Err(Step::Done.into());
+            }
+
+            let at = input.position();
+            let end = dfa.is_match_state(state).then_some(at);
+            (at, state, end, at.saturating_add(SCAN_WINDOW))
+        }
+    };
+
+    loop {
+        let mut gave_up = false;
+        let read = input.read_while(limit, |at, b| {
+            match dfa.next_state(state, b) {
+                Ok(next) => state = next,
+                Err(GaveUp) => {
+                    gave_up = true;
+                    return false;
+                }
+            }
+
+            if dfa.is_match_state(state) {
+                end = Some(at);
+                true
+            } else {
+                !dfa.is_dead_state(state)
+            }
+        });
+
+        if gave_up {
+            return Err(Stop::GaveUp);
+        }
+        match read {
+            Read::Stopped => break,
+            Read::Empty if input.is_partial() && !(end.is_some() && dfa.is_final(state)?) => {
+                return Err(Stop::Pending(Progress::End {
+                    search_start,
+                    state: A::to_index(state),
+                    end,
+                    limit,
+                }));
+            }
+            Read::Empty => break,
+            Read::Limit => {}
+            // The match can't go on without dropping bytes it might have to be searched again from.
+            #[cfg(not(feature = "std"))]
+            Read::Full if end.is_some() => break,
+            // Make room by dropping the oldest byte, which a match could still start in.
+            #[cfg(not(feature = "std"))]
+            Read::Full => input.drop_before(input.window_start() + 1),
+        }
+
+        // Without `std`, the window drops its oldest bytes by itself.
+        #[cfg(feature = "std")]
+        {
+            let live = live.as_mut().filter(|_| end.is_none());
+            if let Some(start) = live.and_then(|live| live.start(input)) {
+                if stop_when_skipped && start > input.window_start() {
+                    // Search the bytes which might still be part of a match again.
+                    input.unread(start);
+                    return Err(Step::Skipped.into());
+                }
+                input.drop_before(start);
+            }
+            too_long(input, input.window_start())?;
+        }
+
+        // Scan again once the window doubled, so this takes linear time overall.
+        let len = input.position() - input.window_start();
+        limit = input.position().saturating_add(len.max(SCAN_WINDOW));
+    }
+
+    let end = end.ok_or(Step::Done)?;
+    input.unread(end);
+    Ok((search_start, end))
+}
+
+/// Runs the unanchored DFA until any match ends, reading one byte at a time
+/// so nothing after it is consumed.
+/// Bytes are dropped from the window once `live` shows that no match can start in them,
+/// the others are kept to search again in case the DFA gives up.
+#[cfg(feature = "std")]
+pub(crate) fn is_match<Haystack: Iterator<Item = u8>, A: Automaton>(
+    input: &mut Input<Haystack>,
+    dfa: &mut A,
+    mut live: Live<'_>,
+) -> Result<bool, GaveUp> {
+    let mut state = dfa.start_state();
+    let mut limit = input.position().saturating_add(SCAN_WINDOW);
+
+    loop {
+        if dfa.is_match_state(state) {
+            return Ok(true);
+        }
+        if dfa.is_dead_state(state) {
+            return Ok(false);
+        }
+
+        let b = match input.read_byte() {
+            Some(b) => b,
+            None => return Ok(false),
+        };
+        state = dfa.next_state(state, b)?;
+
+        if input.position() >= limit {
+            if let Some(start) = live.start(input) {
+                input.drop_before(start);
+            }
+            let len = input.position() - input.window_start();
+            limit = input.position().saturating_add(len.max(SCAN_WINDOW));
+        }
+    }
+}
+
+/// Finds the start of the leftmost match ending at `end` by walking the window backwards,
+/// together with the earliest pattern matching from there.
+/// The match can't start before `search_start`.
+pub(crate) fn find_start<Haystack: Iterator<Item = u8>, A: Automaton>(
+    input: &Input<Haystack>,
+    dfa: &mut A,
+    search_start: u64,
+    end: u64,
+) -> Result<(u64, usize), Stop> {
+    let mut state = dfa.start_state();
+    let mut start = (end, dfa.pattern(state));
+
+    for (i, b) in input.window().rev() {
+        if i < search_start {
+            return Ok(start);
+        }
+
+        state = dfa.next_state(state, b)?;
+
+        if dfa.is_match_state(state) {
+            start = (i, dfa.pattern(state));
+        } else if dfa.is_dead_state(state) {
+            return Ok(start);
+        }
+    }
+
+    // Without `std`, the bytes an earlier start is in may have been dropped to make room.
+    #[cfg(not(feature = "std"))]
+    if input.window_start() > search_start {
+        return Err(Stop::Truncated);
+    }
+
+    Ok(start)
+}
+
+/// Finds the end of the longest match starting at `start`, which is known to match up to `end`.
+/// If the anchored DFA is already in `state`, the window doesn't have to be run through again.
+pub(crate) fn extend_end<Haystack: Iterator<Item = u8>, A: Automaton>(
+    input: &mut Input<Haystack>,
+    dfa: &mut A,
+    search_start: u64,
+    start: u64,
+    mut end: u64,
+    state: Option<usize>,
+) -> Result<u64, Stop> {
+    let mut state = match state {
+        Some(state) => A::from_index(state),
+        None => {
+            let mut state = dfa.start_state();
+            for (_, b) in input.window_from(start) {
+                state = dfa.next_state(state, b)?;
+            }
+            state
+        }
+    };
+
+    loop {
+        // Without `std`, the match ends once the window can't hold more of it.
+        #[cfg(not(feature = "std"))]
+        if input.is_full() {
+            break;
+        }
+
+        let b = match input.read_byte() {
+            Some(b) => b,
+            None if input.is_partial() && !dfa.is_final(state)? => {
+                return Err(Stop::Pending(Progress::Extend {
+                    search_start,
+                    state: A::to_index(state),
+                    start,
+                    end,
+                }));
+            }
+            None => break,
+        };
+
+        state = dfa.next_state(state, b)?;
+
+        if dfa.is_match_state(state) {
+            end = input.position();
+        } else if dfa.is_dead_state(state) {
+            break;
+        }
+
+        #[cfg(feature = "std")]
+        too_long(input, start)?;
+    }
+
+    input.unread(end);
+    Ok(end)
+}
+
+/// Fails if the window from `start` on holds more bytes than the input keeps for a match,
+/// with some slack so the window isn't searched again too often, or more than it may hold at all.
+#[cfg(feature = "std")]
+fn too_long<Haystack: Iterator<Item = u8>>(
+    input: &Input<Haystack>,
+    start: u64,
+) -> Result<(), Stop> {
+    let len = input.position() - start;
+    let too_long = match (input.max_len(), input.max_window()) {
+        (Some(max_len), _) => len > max_len.saturating_add(SCAN_WINDOW),
+        (None, Some(max_window)) => len > max_window,
+        (None, None) => false,
+    };
+
+    match too_long {
+        true => Err(Stop::TooLong),
+        false => Ok(()),
+    }
+}
@@ -0,0 +1,238 @@
+//! Searching haystacks in legacy encodings, decoded to UTF-8 with `encoding_rs` on the fly.
+
+use std::{collections::VecDeque, ops::Range};
+
+use encoding_rs::{Decoder, DecoderResult, Encoding};
+
+use crate::{transcode::Counter, usize_range, Matches, Step};
+
+/// An iterator over the matches in an encoded haystack, with ranges of the encoded bytes,
+/// see [Regex::matches_encoded](crate::Regex::matches_encoded).
+#[derive(Debug)]
+pub struct EncodedMatches<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Decoded<Haystack>>,
+    counter: Counter,
+}
+
+/// The UTF-8 decoding of an encoded haystack, keeping track of where each character came from.
+#[derive(Debug)]
+pub(crate) struct Decoded<Haystack> {
+    haystack: Haystack,
+    /// The encoding declared for a haystack without a BOM.
+    encoding: &'static Encoding,
+    /// Created once the BOM was sniffed.
+    decoder: Option<Decoder>,
+    /// Decoded bytes which weren't returned yet, from `pos` on.
+    out: Vec<u8>,
+    pos: usize,
+    /// The number of encoded bytes decoded.
+    consumed: u64,
+    /// Where the encoded bytes of the next character start.
+    char_start: u64,
+    finished: bool,
+    /// The encoded bytes of the characters decoded but not counted yet.
+    sources: VecDeque<Range<u64>>,
+    /// The number of characters which were dropped from `sources`.
+    dropped: u64,
+    /// Where the encoded bytes of the dropped characters end.
+    dropped_end: u64,
+}
+
+impl<Haystack: Iterator<Item = u8>> Decoded<Haystack> {
+    pub(crate) fn new(haystack: Haystack, encoding: &'static Encoding) -> Decoded<Haystack> {
+        Decoded {
+            haystack,
+            encoding,
+            decoder: None,
+            out: vec![],
+            pos: 0,
+            consumed: 0,
+            char_start: 0,
+            finished: false,
+            sources: VecDeque::new(),
+            dropped: 0,
+            dropped_end: 0,
+        }
+    }
+
+    /// Sniffs the BOM, which overrides the declared encoding, and decodes the bytes after it.
+    fn sniff(&mut self) {
+        let mut bom = Vec::with_capacity(3);
+        bom.extend(self.haystack.by_ref().take(3));
+
+        let (encoding, len) = Encoding::for_bom(&bom).unwrap_or((self.encoding, 0));
+        self.decoder = Some(encoding.new_decoder_without_bom_handling());
+        self.consumed = len as u64;
+        self.char_start = len as u64;
+        self.dropped_end = len as u64;
+
+        for &b in &bom[len..] {
+            self.decode(Some(b));
+        }
+    }
+
+    /// Decodes the next encoded byte, or the end of the haystack.
+    fn decode(&mut self, b: Option<u8>) {
+        let decoder = self.decoder.as_mut().expect("the BOM was sniffed");
+        let mut src = b.as_slice();
+        loop {
+            let start = self.out.len();
+            let max = decoder
+                .max_utf8_buffer_length_without_replacement(src.len())
+                .expect("one byte doesn't overflow");
+            self.out.resize(start + max, 0);
+
+            let (result, read, written) = decoder.decode_to_utf8_without_replacement(
+                src,
+                &mut self.out[start..],
+                b.is_none(),
+            );
+            self.out.truncate(start + written);
+            self.consumed += read as u64;
+            src = &src[read..];
+
+            // The characters decoded are made of the bytes since the last one,
+            // even if one byte completes several of them.
+            let chars = self.out[start..].iter().filter(|&&b| b & 0xc0 != 0x80);
+            for _ in chars {
+                self.sources.push_back(self.char_start..self.consumed);
+            }
+            if written > 0 {
+                self.char_start = self.consumed;
+            }
+
+            match result {
+                DecoderResult::InputEmpty => return,
+                DecoderResult::OutputFull => unreachable!("the output fits the buffer"),
+                // The bytes read after the malformed ones belong to the next character.
+                DecoderResult::Malformed(_, after) => {
+                    let end = (self.consumed - u64::from(after)).max(self.char_start);
+                    self.out.extend_from_slice("\u{fffd}".as_bytes());
+                    self.sources.push_back(self.char_start..end);
+                    self.char_start = end;
+                }
+            }
+        }
+    }
+
+    /// Where the encoded bytes of the character with index `i` start.
+    fn start_of(&self, i: u64) -> u64 {
+        self.sources[(i - self.dropped) as usize].start
+    }
+
+    /// Where the encoded bytes of the first `i` characters end.
+    fn end_of(&self, i: u64) -> u64 {
+        match i - self.dropped {
+            0 => self.dropped_end,
+            n => self.sources[n as usize - 1].end,
+        }
+    }
+
+    /// Forgets where the characters before index `i` came from.
+    fn drop_before(&mut self, i: u64) {
+        while self.dropped < i {
+            let source = self.sources.pop_front().expect("the character was decoded");
+            self.dropped_end = source.end;
+            self.dropped += 1;
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for Decoded<Haystack> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&b) = self.out.get(self.pos) {
+                self.pos += 1;
+                return Some(b);
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            self.out.clear();
+            self.pos = 0;
+
+            if self.decoder.is_none() {
+                self.sniff();
+                continue;
+            }
+
+            let b = self.haystack.next();
+            self.finished = b.is_none();
+            self.decode(b);
+        }
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> EncodedMatches<'r, Haystack> {
+    pub(crate) fn new(mut matches: Matches<'r, Decoded<Haystack>>) -> EncodedMatches<'r, Haystack> {
+        // Matches are mapped back to the encoded bytes by counting the decoded characters before them,
+        // which also lets the decoder forget where skipped characters came from.
+        matches.stop_when_skipped = true;
+        matches.keep_match_bytes();
+
+        EncodedMatches {
+            matches,
+            counter: Counter::new(|_| 1),
+        }
+    }
+
+    /// The number of characters counted so far, if the bytes counted end on a character boundary.
+    fn chars(&self) -> Option<u64> {
+        self.counter.boundary().map(|chars| chars as u64)
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for EncodedMatches<'_, Haystack> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.matches.step() {
+                Step::Match(mat) => {
+                    let input = &self.matches.input;
+                    for (_, b) in input.window().take_while(|&(i, _)| i < mat.start) {
+                        self.counter.count(b);
+                    }
+                    let start = self.chars();
+                    for (_, b) in input.window_from(mat.start) {
+                        self.counter.count(b);
+                    }
+                    let end = self.chars();
+
+                    let decoded = self.matches.input.haystack_mut();
+                    let range = match (start, end) {
+                        // Skip bytes which don't encode a character, like a BOM, at the start.
+                        (Some(start), Some(end)) if start < end => {
+                            Some(decoded.start_of(start)..decoded.end_of(end))
+                        }
+                        (Some(start), Some(_)) => {
+                            Some(decoded.end_of(start)..decoded.end_of(start))
+                        }
+                        _ => None,
+                    };
+
+                    if let Some(end) = end {
+                        decoded.drop_before(end);
+                    }
+                    if let Some(range) = range {
+                        return Some(usize_range(range));
+                    }
+                }
+                Step::Skipped => {
+                    for (_, b) in self.matches.input.window() {
+                        self.counter.count(b);
+                    }
+                    if let Some(chars) = self.chars() {
+                        self.matches.input.haystack_mut().drop_before(chars);
+                    }
+                }
+                Step::Done => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Searching haystacks whose bytes can fail to be produced.
+
+use core::ops::Range;
+
+use crate::Matches;
+
+/// An iterator over the matches in a fallible haystack, see [Regex::try_matches](crate::Regex::try_matches).
+///
+/// The first error of the haystack is returned after the matches which don't depend on the failed byte,
+/// in place of the match it interrupted, after which the iterator ends.
+#[derive(Debug)]
+pub struct TryMatches<'r, Haystack: Iterator<Item = Result<u8, E>>, E> {
+    matches: Matches<'r, Fallible<Haystack, E>>,
+    done: bool,
+}
+
+/// The bytes of a fallible haystack, up to its first error.
+#[derive(Debug)]
+pub(crate) struct Fallible<Haystack, E> {
+    haystack: Haystack,
+    error: Option<E>,
+}
+
+impl<Haystack, E> Fallible<Haystack, E> {
+    pub(crate) fn new(haystack: Haystack) -> Fallible<Haystack, E> {
+        Fallible {
+            haystack,
+            error: None,
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = Result<u8, E>>, E> Iterator for Fallible<Haystack, E> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.haystack.next()? {
+            Ok(b) => Some(b),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = Result<u8, E>>, E> TryMatches<'r, Haystack, E> {
+    pub(crate) fn new(
+        mut matches: Matches<'r, Fallible<Haystack, E>>,
+    ) -> TryMatches<'r, Haystack, E> {
+        // Searches wait at the failed byte instead of treating it as the end of the haystack,
+        // so the matches found before it are known not to depend on it.
+        matches.input.set_partial(true);
+        TryMatches {
+            matches,
+            done: false,
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = Result<u8, E>>, E> Iterator for TryMatches<'_, Haystack, E> {
+    type Item = Result<Range<usize>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            if let Some(mat) = self.matches.next() {
+                return Some(Ok(mat));
+            }
+
+            // The search waits for the bytes after the error, so the match it interrupted is dropped.
+            if let Some(err) = self.matches.input.haystack_mut().error.take() {
+                self.done = true;
+                return Some(Err(err));
+            }
+
+            if !self.matches.input.is_partial() {
+                self.done = true;
+                return None;
+            }
+
+            // The haystack ended, so finish the search in progress.
+            self.matches.input.set_partial(false);
+        }
+    }
+}
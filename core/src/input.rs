@@ -0,0 +1,529 @@
+#[cfg(not(feature = "std"))]
+use core::ops::RangeFrom;
+#[cfg(feature = "std")]
+use std::{collections::VecDeque, ops::Range};
+
+#[cfg(feature = "std")]
+type Buffer = VecDeque<u8>;
+#[cfg(not(feature = "std"))]
+type Buffer = Ring;
+
+/// How many bytes the window can hold without `std`, see [Read::Full].
+#[cfg(not(feature = "std"))]
+const WINDOW_CAPACITY: usize = 256;
+
+/// The most bytes read ahead at once, if the haystack has that many ready.
+#[cfg(feature = "std")]
+const READ_AHEAD: usize = 4096;
+
+/// Appends at most the given number of bytes the haystack has ready to the lookahead,
+/// see [Input::set_fill].
+#[cfg(feature = "std")]
+pub(crate) type Fill<Haystack> = fn(&mut Haystack, &mut VecDeque<u8>, usize);
+
+/// The haystack being searched, buffering bytes which might have to be searched again.
+#[derive(Debug)]
+pub(crate) struct Input<Haystack> {
+    haystack: Haystack,
+    #[cfg(feature = "std")]
+    fill: Fill<Haystack>,
+    /// Bytes read past the end of the last match, which still have to be searched.
+    lookahead: Buffer,
+    /// Bytes which might be part of the next match, starting at `window_start`.
+    window: Buffer,
+    window_start: u64,
+    next_index: u64,
+    /// Whether more bytes might follow once the haystack ran out, see [Input::set_partial].
+    partial: bool,
+    /// How many bytes of a match have to be kept, see [Input::set_max_len].
+    #[cfg(feature = "std")]
+    max_len: Option<u64>,
+    /// How many bytes the window may hold while a match might start in them, see [Input::set_max_window].
+    #[cfg(feature = "std")]
+    max_window: Option<u64>,
+    /// Bytes in the middle of the window which were dropped, see [Input::drop_between].
+    #[cfg(feature = "std")]
+    gap: Range<u64>,
+    /// The bytes right before the next byte to be read.
+    #[cfg(feature = "std")]
+    behind: Behind,
+    /// The bytes right before the window.
+    #[cfg(feature = "std")]
+    behind_window: Behind,
+}
+
+/// Why [Input::read_while] stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Read {
+    /// The callback returned false.
+    Stopped,
+    /// The position reached the limit.
+    Limit,
+    /// The haystack ran out.
+    Empty,
+    /// The window can't hold another byte, see [Input::drop_before].
+    #[cfg(not(feature = "std"))]
+    Full,
+}
+
+/// A fixed-capacity queue of bytes, used instead of `VecDeque` without `std`.
+#[cfg(not(feature = "std"))]
+#[derive(Debug)]
+struct Ring {
+    bytes: [u8; WINDOW_CAPACITY],
+    head: usize,
+    len: usize,
+}
+
+/// The last few bytes before a position, enough to decode the character ending there.
+/// They are packed into an integer, nearest in the lowest byte, as they are updated for every read.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+struct Behind {
+    bytes: u32,
+    len: usize,
+}
+
+/// How many bytes [Behind] keeps.
+#[cfg(feature = "std")]
+const BEHIND_LEN: usize = 4;
+
+impl<Haystack: Iterator<Item = u8>> Input<Haystack> {
+    /// Creates the input, with `base` as the index of the first byte of the haystack.
+    pub(crate) fn new(haystack: Haystack, base: u64) -> Input<Haystack> {
+        Input {
+            haystack,
+            #[cfg(feature = "std")]
+            fill: fill_from_iter,
+            lookahead: Buffer::new(),
+            window: Buffer::new(),
+            window_start: base,
+            next_index: base,
+            partial: false,
+            #[cfg(feature = "std")]
+            max_len: None,
+            #[cfg(feature = "std")]
+            max_window: None,
+            #[cfg(feature = "std")]
+            gap: 0..0,
+            #[cfg(feature = "std")]
+            behind: Behind::default(),
+            #[cfg(feature = "std")]
+            behind_window: Behind::default(),
+        }
+    }
+
+    /// Records the bytes right before the haystack, nearest last, for look-around assertions.
+    /// This has to happen before anything is read.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_context(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        for b in bytes {
+            self.behind.push(b);
+        }
+        self.behind_window = self.behind;
+    }
+
+    /// Sets whether more bytes might follow once the haystack ran out,
+    /// so a search has to wait for them instead of ending there.
+    pub(crate) fn set_partial(&mut self, yes: bool) {
+        self.partial = yes;
+    }
+
+    pub(crate) fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Sets how many bytes at the start of a match have to be kept,
+    /// so the search can stop buffering once a match gets longer.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_max_len(&mut self, max_len: Option<u64>) {
+        self.max_len = max_len;
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn max_len(&self) -> Option<u64> {
+        self.max_len
+    }
+
+    /// Sets how many bytes the window may hold while a match might still start in them.
+    /// Past that, only where matches are is kept track of, so their bytes can be dropped.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_max_window(&mut self, max_window: Option<u64>) {
+        self.max_window = max_window;
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn max_window(&self) -> Option<u64> {
+        self.max_window
+    }
+
+    /// Whether the window holds more bytes than it may, see [Input::set_max_window].
+    #[cfg(feature = "std")]
+    pub(crate) fn is_over_max_window(&self) -> bool {
+        matches!(self.max_window, Some(max_window) if self.next_index - self.window_start > max_window)
+    }
+
+    /// Sets how bytes are read ahead, so haystacks made of slices can copy them in bulk.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_fill(&mut self, fill: Fill<Haystack>) {
+        self.fill = fill;
+    }
+
+    /// Returns the haystack, e.g. to add bytes to it.
+    pub(crate) fn haystack_mut(&mut self) -> &mut Haystack {
+        &mut self.haystack
+    }
+
+    /// The index of the next byte to be read.
+    pub(crate) fn position(&self) -> u64 {
+        self.next_index
+    }
+
+    /// The index of the first byte in the window.
+    pub(crate) fn window_start(&self) -> u64 {
+        self.window_start
+    }
+
+    /// Returns the next byte, preferring bytes that were read ahead before.
+    /// The byte is not kept in the window.
+    pub(crate) fn next_byte(&mut self) -> Option<u8> {
+        let b = self
+            .lookahead
+            .pop_front()
+            .or_else(|| self.haystack.next())?;
+        self.next_index += 1;
+        #[cfg(feature = "std")]
+        self.behind.push(b);
+        Some(b)
+    }
+
+    /// Reads the next byte into the window.
+    ///
+    /// # Panics
+    ///
+    /// Without `std`, panics if the window is full.
+    pub(crate) fn read_byte(&mut self) -> Option<u8> {
+        let b = self.next_byte()?;
+        self.window.push_back(b);
+        Some(b)
+    }
+
+    /// Whether the window can't hold another byte without dropping one first.
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn is_full(&self) -> bool {
+        self.window.len == WINDOW_CAPACITY
+    }
+
+    /// Reads bytes into the window until `f` returns false for one, the position reaches `until`,
+    /// the haystack runs out, or without `std`, the window is full.
+    /// `f` gets each byte together with the position after it.
+    ///
+    /// As many bytes as the haystack has ready are read ahead at once,
+    /// so `f` runs over slices instead of going through [Input::read_byte].
+    pub(crate) fn read_while(&mut self, until: u64, mut f: impl FnMut(u64, u8) -> bool) -> Read {
+        #[cfg(not(feature = "std"))]
+        while self.next_index < until {
+            if self.is_full() {
+                return Read::Full;
+            }
+            match self.read_byte() {
+                Some(b) if !f(self.next_index, b) => return Read::Stopped,
+                Some(_) => {}
+                None => return Read::Empty,
+            }
+        }
+
+        #[cfg(feature = "std")]
+        while self.next_index < until {
+            if self.lookahead.is_empty() {
+                (self.fill)(&mut self.haystack, &mut self.lookahead, READ_AHEAD);
+                if self.lookahead.is_empty() {
+                    return Read::Empty;
+                }
+            }
+
+            let (bytes, _) = self.lookahead.as_slices();
+            let bytes = &bytes[..bytes.len().min(distance(until - self.next_index))];
+            let mut read = 0;
+            let mut stopped = false;
+            for &b in bytes {
+                read += 1;
+                if !f(self.next_index + read as u64, b) {
+                    stopped = true;
+                    break;
+                }
+            }
+
+            self.next_index += read as u64;
+            let bytes = &self.lookahead.as_slices().0[..read];
+            self.behind.extend(bytes);
+            if read == self.lookahead.len() {
+                self.window.append(&mut self.lookahead);
+            } else {
+                self.window.extend(bytes);
+                self.lookahead.drain(..read);
+            }
+
+            if stopped {
+                return Read::Stopped;
+            }
+        }
+
+        // No byte can follow the one ending at `u64::MAX`.
+        if until == u64::MAX {
+            Read::Empty
+        } else {
+            Read::Limit
+        }
+    }
+
+    /// Puts the bytes of the window from `index` on back to be read again.
+    pub(crate) fn unread(&mut self, index: u64) {
+        #[cfg(not(feature = "std"))]
+        assert!(
+            index >= self.window_start,
+            "unread bytes which were dropped"
+        );
+        #[cfg(feature = "std")]
+        assert!(
+            self.gap.is_empty() || index >= self.gap.end,
+            "unread bytes which were dropped"
+        );
+        for _ in index..self.next_index {
+            let b = self.window.pop_back().expect("unread more than was read");
+            self.lookahead.push_front(b);
+        }
+        self.next_index = index;
+
+        #[cfg(feature = "std")]
+        {
+            let (front, back) = self.window.as_slices();
+            self.behind = self.behind_window;
+            self.behind.extend(front);
+            self.behind.extend(back);
+        }
+    }
+
+    /// Drops the window, so it starts at the next byte to be read.
+    pub(crate) fn clear_window(&mut self) {
+        self.window.clear();
+        self.window_start = self.next_index;
+        #[cfg(feature = "std")]
+        {
+            self.behind_window = self.behind;
+            self.gap = 0..0;
+        }
+    }
+
+    /// Drops the bytes of the window before `index`.
+    pub(crate) fn drop_before(&mut self, index: u64) {
+        #[cfg(feature = "std")]
+        {
+            let len = self.offset(index);
+            for &b in self.window.range(len.saturating_sub(BEHIND_LEN)..len) {
+                self.behind_window.push(b);
+            }
+            self.window.drain(..len);
+        }
+        #[cfg(not(feature = "std"))]
+        for _ in 0..self.offset(index) {
+            self.window.pop_front();
+        }
+        self.window_start = index;
+    }
+
+    /// Drops the bytes of the window from `from` up to `to`, which don't have to be looked at again.
+    /// The last few of them are kept for look-behind assertions at `to`.
+    /// There is only room for one gap, so this does nothing unless it touches the current one.
+    #[cfg(feature = "std")]
+    pub(crate) fn drop_between(&mut self, from: u64, to: u64) {
+        let to = to.saturating_sub(BEHIND_LEN as u64);
+        let gap = if self.gap.is_empty() {
+            from..to
+        } else if from <= self.gap.end && to >= self.gap.start {
+            from.min(self.gap.start)..to.max(self.gap.end)
+        } else {
+            return;
+        };
+
+        if gap.is_empty() || gap.start < self.window_start || gap.end > self.next_index {
+            return;
+        }
+
+        // The new gap covers the current one, which isn't in the window anymore.
+        let dropped = self.gap.end - self.gap.start;
+        let range = distance(gap.start - self.window_start)
+            ..distance(gap.end - dropped - self.window_start);
+        self.window.drain(range);
+        self.gap = gap;
+    }
+
+    /// Returns where the byte at `index` is in the window.
+    fn offset(&self, index: u64) -> usize {
+        #[cfg(feature = "std")]
+        let index = if index >= self.gap.end {
+            index - (self.gap.end - self.gap.start)
+        } else {
+            assert!(index < self.gap.start, "the byte was dropped");
+            index
+        };
+        distance(index - self.window_start)
+    }
+
+    /// Returns the bytes in the window, together with their index.
+    pub(crate) fn window(&self) -> impl DoubleEndedIterator<Item = (u64, u8)> + '_ {
+        self.window_from(self.window_start)
+    }
+
+    /// Returns the bytes in the window from `start` on, together with their index.
+    pub(crate) fn window_from(
+        &self,
+        start: u64,
+    ) -> impl DoubleEndedIterator<Item = (u64, u8)> + '_ {
+        // Indices after the gap are shifted by its length.
+        #[cfg(feature = "std")]
+        let (gap_start, gap_len) = match start < self.gap.start {
+            true => (self.gap.start, self.gap.end - self.gap.start),
+            false => (u64::MAX, 0),
+        };
+        #[cfg(not(feature = "std"))]
+        let (gap_start, gap_len) = (u64::MAX, 0);
+
+        self.window
+            .range(self.offset(start)..)
+            .copied()
+            .enumerate()
+            .map(move |(i, b)| {
+                let index = start + i as u64;
+                match index >= gap_start {
+                    true => (index + gap_len, b),
+                    false => (index, b),
+                }
+            })
+    }
+
+    /// Returns the byte at `index`, reading ahead if it wasn't read yet.
+    /// Only the window and the last few bytes before it or before the next byte can be looked at again.
+    #[cfg(feature = "std")]
+    pub(crate) fn byte_at(&mut self, index: u64) -> Option<u8> {
+        if index >= self.next_index {
+            let ahead = distance(index - self.next_index);
+            while self.lookahead.len() <= ahead {
+                let b = self.haystack.next()?;
+                self.lookahead.push_back(b);
+            }
+            return Some(self.lookahead[ahead]);
+        }
+
+        if let Some(b) = self.behind.get(distance(self.next_index - index)) {
+            return Some(b);
+        }
+
+        if index >= self.window_start {
+            if self.gap.contains(&index) {
+                return None;
+            }
+            return self.window.get(self.offset(index)).copied();
+        }
+
+        self.behind_window.get(distance(self.window_start - index))
+    }
+}
+
+/// Reads ahead by calling [Iterator::next], as many bytes as the size hint promises.
+#[cfg(feature = "std")]
+fn fill_from_iter<Haystack: Iterator<Item = u8>>(
+    haystack: &mut Haystack,
+    lookahead: &mut VecDeque<u8>,
+    max: usize,
+) {
+    // Bytes the haystack doesn't promise might not be there yet, so wait for one at a time.
+    let ready = haystack.size_hint().0.clamp(1, max);
+    lookahead.extend(haystack.by_ref().take(ready));
+}
+
+/// Converts the distance between two indices, saturating if it doesn't fit.
+/// Buffered bytes always fit, so a saturated distance is out of reach either way.
+fn distance(distance: u64) -> usize {
+    usize::try_from(distance).unwrap_or(usize::MAX)
+}
+
+#[cfg(not(feature = "std"))]
+impl Ring {
+    fn new() -> Ring {
+        Ring {
+            bytes: [0; WINDOW_CAPACITY],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn slot(&self, i: usize) -> usize {
+        (self.head + i) % WINDOW_CAPACITY
+    }
+
+    fn push_back(&mut self, b: u8) {
+        assert!(self.len < WINDOW_CAPACITY, "ring is full");
+        self.bytes[self.slot(self.len)] = b;
+        self.len += 1;
+    }
+
+    fn push_front(&mut self, b: u8) {
+        assert!(self.len < WINDOW_CAPACITY, "ring is full");
+        self.head = self.slot(WINDOW_CAPACITY - 1);
+        self.bytes[self.head] = b;
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let b = self.bytes[self.head];
+        self.head = self.slot(1);
+        self.len -= 1;
+        Some(b)
+    }
+
+    fn pop_back(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.bytes[self.slot(self.len)])
+    }
+
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    fn range(
+        &self,
+        range: RangeFrom<usize>,
+    ) -> impl DoubleEndedIterator<Item = &u8> + ExactSizeIterator {
+        (range.start..self.len).map(move |i| &self.bytes[self.slot(i)])
+    }
+}
+
+#[cfg(feature = "std")]
+impl Behind {
+    fn push(&mut self, b: u8) {
+        self.bytes = self.bytes << 8 | u32::from(b);
+        self.len = (self.len + 1).min(BEHIND_LEN);
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        for &b in &bytes[bytes.len().saturating_sub(BEHIND_LEN)..] {
+            self.push(b);
+        }
+    }
+
+    /// Returns the byte `back` bytes before the position, counting from 1.
+    fn get(&self, back: usize) -> Option<u8> {
+        match back {
+            1..=BEHIND_LEN if back <= self.len => Some((self.bytes >> (8 * (back - 1))) as u8),
+            _ => None,
+        }
+    }
+}
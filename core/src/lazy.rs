@@ -0,0 +1,302 @@
+//! A lazy DFA, determinizing the [Nfa] while searching and caching the states found.
+//! Unlike a dense DFA, building it takes no time and its memory is bounded by the cache.
+
+use std::{collections::HashMap, mem};
+
+use crate::{
+    dfa::{Automaton, GaveUp},
+    nfa::{Nfa, State, StateId},
+    pikevm::SparseSet,
+};
+
+type LazyStateId = u32;
+
+/// The state without any NFA states, which never matches.
+const DEAD: LazyStateId = 0;
+/// Marks a transition which wasn't determinized yet.
+const UNKNOWN: LazyStateId = LazyStateId::MAX;
+/// Stands for the implicit `(?s:.)*?` prefix of an unanchored search within a DFA state.
+const UNANCHORED: StateId = StateId::MAX;
+
+/// The cache may be cleared this often before the DFA is allowed to give up.
+const MIN_CLEARS: usize = 3;
+/// If fewer transitions per state were taken since the last clear, the cache is thrashing.
+const MIN_STEPS_PER_STATE: usize = 10;
+
+/// The states of a lazy DFA determinized so far.
+#[derive(Debug, Clone)]
+pub(crate) struct Cache {
+    /// Whether this finds the longest match at the start of the input,
+    /// instead of the end of the leftmost-first match anywhere.
+    anchored: bool,
+    /// Maps each byte to its equivalence class, bytes in a class are never distinguished.
+    classes: [u8; 256],
+    stride: usize,
+    /// The NFA states of each DFA state, in order of priority.
+    states: Vec<Box<[StateId]>>,
+    is_match: Vec<bool>,
+    ids: HashMap<Box<[StateId]>, LazyStateId>,
+    /// The transitions of each state, one per byte class.
+    transitions: Vec<LazyStateId>,
+    start: LazyStateId,
+    /// The number of bytes the states may use before the cache is cleared.
+    capacity: usize,
+    memory: usize,
+    clears: usize,
+    /// Transitions taken since the cache was cleared last.
+    steps: usize,
+    set: SparseSet,
+    stack: Vec<StateId>,
+    /// The NFA states of the DFA state being built.
+    list: Vec<StateId>,
+}
+
+/// A lazy DFA, made up of the NFA and the cache of its states.
+pub(crate) struct Lazy<'a> {
+    nfa: &'a Nfa,
+    cache: &'a mut Cache,
+}
+
+impl Cache {
+    pub(crate) fn new(nfa: &Nfa, anchored: bool, capacity: usize) -> Cache {
+        let classes = byte_classes(nfa);
+        let mut cache = Cache {
+            anchored,
+            classes,
+            stride: usize::from(classes[255]) + 1,
+            states: vec![],
+            is_match: vec![],
+            ids: HashMap::new(),
+            transitions: vec![],
+            start: DEAD,
+            capacity,
+            memory: 0,
+            clears: 0,
+            steps: 0,
+            set: SparseSet::new(nfa.len()),
+            stack: vec![],
+            list: vec![],
+        };
+        cache.init(nfa);
+        cache
+    }
+
+    /// Adds the dead and the start state, so their IDs stay the same when clearing.
+    fn init(&mut self, nfa: &Nfa) {
+        self.list.clear();
+        self.insert(nfa);
+
+        self.set.clear();
+        self.list.clear();
+        if !self.add(nfa, nfa.start()) && !self.anchored {
+            self.list.push(UNANCHORED);
+        }
+        self.start = self.insert(nfa);
+    }
+
+    /// Drops all states but the dead and the start state.
+    /// Gives up if this happens too often to make progress.
+    fn clear(&mut self, nfa: &Nfa) -> Result<(), GaveUp> {
+        if self.clears >= MIN_CLEARS && self.steps < MIN_STEPS_PER_STATE * self.states.len() {
+            return Err(GaveUp);
+        }
+
+        self.clears += 1;
+        self.steps = 0;
+        self.states.clear();
+        self.is_match.clear();
+        self.ids.clear();
+        self.transitions.clear();
+        self.memory = 0;
+
+        // Keep the state being built.
+        let list = mem::take(&mut self.list);
+        self.init(nfa);
+        self.list = list;
+        Ok(())
+    }
+
+    /// Returns the state reached from `from` on `b`, determinizing it if needed.
+    fn next(&mut self, nfa: &Nfa, from: LazyStateId, b: u8) -> Result<LazyStateId, GaveUp> {
+        self.steps += 1;
+
+        let i = from as usize * self.stride + usize::from(self.classes[usize::from(b)]);
+        let to = self.transitions[i];
+        if to != UNKNOWN {
+            return Ok(to);
+        }
+
+        self.set.clear();
+        self.list.clear();
+
+        let states = mem::take(&mut self.states[from as usize]);
+        for &id in states.iter() {
+            let matched = if id == UNANCHORED {
+                // Start a new match here, with lower priority than the ones in progress.
+                let matched = self.add(nfa, nfa.start());
+                if !matched {
+                    self.list.push(UNANCHORED);
+                }
+                matched
+            } else {
+                match *nfa.state(id) {
+                    State::Range { start, end, next } if start <= b && b <= end => {
+                        self.add(nfa, next)
+                    }
+                    _ => false,
+                }
+            };
+
+            if matched && !self.anchored {
+                // States with lower priority can't win anymore.
+                break;
+            }
+        }
+        self.states[from as usize] = states;
+
+        if self.memory > self.capacity {
+            self.clear(nfa)?;
+            return Ok(self.insert(nfa));
+        }
+
+        let to = self.insert(nfa);
+        self.transitions[i] = to;
+        Ok(to)
+    }
+
+    /// Adds the epsilon closure of `id` to the list, in order of priority.
+    /// Returns whether a match was reached when searching for the leftmost-first match,
+    /// in which case states with lower priority are dropped.
+    fn add(&mut self, nfa: &Nfa, id: StateId) -> bool {
+        self.stack.push(id);
+
+        while let Some(id) = self.stack.pop() {
+            if !self.set.insert(id) {
+                continue;
+            }
+
+            match nfa.state(id) {
+                State::Range { .. } => self.list.push(id),
+                State::Match { .. } => {
+                    self.list.push(id);
+                    if !self.anchored {
+                        self.stack.clear();
+                        return true;
+                    }
+                }
+                State::Union { alternates } => self.stack.extend(alternates.iter().rev()),
+                State::Capture { next, .. } => self.stack.push(*next),
+                State::Look { .. } => unreachable!("look-around is searched with the Pike VM"),
+            }
+        }
+
+        false
+    }
+
+    /// Adds the state made up of the NFA states in the list, unless it exists already.
+    fn insert(&mut self, nfa: &Nfa) -> LazyStateId {
+        if self.anchored {
+            // Priorities don't matter for longest matches, so equal sets are merged.
+            self.list.sort_unstable();
+        }
+
+        if let Some(&id) = self.ids.get(self.list.as_slice()) {
+            return id;
+        }
+
+        let id = self.states.len() as LazyStateId;
+        let states: Box<[StateId]> = self.list.as_slice().into();
+
+        self.memory += 2 * mem::size_of_val(&*states)
+            + self.stride * mem::size_of::<LazyStateId>()
+            + mem::size_of::<Box<[StateId]>>();
+
+        self.is_match.push(
+            states
+                .iter()
+                .any(|&id| id != UNANCHORED && matches!(nfa.state(id), State::Match { .. })),
+        );
+        self.ids.insert(states.clone(), id);
+        self.states.push(states);
+        self.transitions
+            .resize(self.transitions.len() + self.stride, UNKNOWN);
+        id
+    }
+}
+
+impl<'a> Lazy<'a> {
+    pub(crate) fn new(nfa: &'a Nfa, cache: &'a mut Cache) -> Lazy<'a> {
+        Lazy { nfa, cache }
+    }
+}
+
+impl Automaton for Lazy<'_> {
+    type State = LazyStateId;
+
+    fn start_state(&mut self) -> LazyStateId {
+        self.cache.start
+    }
+
+    fn next_state(&mut self, state: LazyStateId, b: u8) -> Result<LazyStateId, GaveUp> {
+        self.cache.next(self.nfa, state, b)
+    }
+
+    fn is_match_state(&self, state: LazyStateId) -> bool {
+        self.cache.is_match[state as usize]
+    }
+
+    fn is_dead_state(&self, state: LazyStateId) -> bool {
+        state == DEAD
+    }
+
+    fn is_final(&mut self, state: LazyStateId) -> Result<bool, GaveUp> {
+        // Only byte ranges and starting a new match lead anywhere, so this needs no transitions,
+        // which could clear the cache and invalidate `state`.
+        let states = &self.cache.states[state as usize];
+        Ok(!states
+            .iter()
+            .any(|&id| id == UNANCHORED || matches!(self.nfa.state(id), State::Range { .. })))
+    }
+
+    fn pattern(&self, state: LazyStateId) -> usize {
+        let patterns = self.cache.states[state as usize]
+            .iter()
+            .filter(|&&id| id != UNANCHORED)
+            .filter_map(|&id| match *self.nfa.state(id) {
+                State::Match { pattern } => Some(pattern),
+                _ => None,
+            });
+        patterns.min().unwrap_or(0)
+    }
+
+    fn to_index(state: LazyStateId) -> usize {
+        state as usize
+    }
+
+    fn from_index(index: usize) -> LazyStateId {
+        index as LazyStateId
+    }
+}
+
+/// Splits the bytes into classes, such that no transition of the NFA distinguishes bytes in a class.
+fn byte_classes(nfa: &Nfa) -> [u8; 256] {
+    let mut boundaries = [false; 256];
+    for id in 0..nfa.len() {
+        if let State::Range { start, end, .. } = *nfa.state(id) {
+            boundaries[usize::from(start)] = true;
+            if let Some(after) = end.checked_add(1) {
+                boundaries[usize::from(after)] = true;
+            }
+        }
+    }
+
+    let mut classes = [0; 256];
+    let mut class = 0u8;
+    for b in 1..256 {
+        if boundaries[b] {
+            class += 1;
+        }
+        classes[b] = class;
+    }
+    classes
+}
@@ -0,0 +1,1862 @@
+//! The implementation of [hotsauce](https://docs.rs/hotsauce), which re-exports all of it.
+//! It's a crate of its own so `hotsauce-macros` can compile regexes with it.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs, unreachable_pub)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::{fmt, iter::Rev, ops::Range};
+#[cfg(feature = "std")]
+use std::{cell::Cell, convert::TryFrom, slice};
+
+#[cfg(feature = "std")]
+use regex_automata::dense;
+use regex_automata::DenseDFA;
+#[cfg(feature = "std")]
+use regex_syntax::{hir::Hir, ParserBuilder};
+
+#[cfg(feature = "std")]
+use dfa::CompactDfa;
+use dfa::Stop;
+use input::Input;
+#[cfg(feature = "std")]
+use lazy::Lazy;
+#[cfg(feature = "std")]
+use nfa::Nfa;
+
+#[cfg(feature = "std")]
+pub use captures::{CaptureMatches, Captures};
+#[cfg(feature = "std")]
+pub use chunks::ReaderMatches;
+pub use chunks::{Chunks, RevChunks};
+pub use cursor::{Cursor, SliceCursor};
+#[cfg(feature = "encoding_rs")]
+pub use encoding::EncodedMatches;
+pub use fallible::TryMatches;
+#[cfg(feature = "std")]
+pub use owned::{MatchTooLong, OwnedMatch, WithBytes};
+#[cfg(feature = "std")]
+pub use position::{LineColumn, Match, Positions};
+#[cfg(feature = "std")]
+pub use replace::{NoExpand, ReplaceAll, Replacer};
+#[cfg(feature = "std")]
+pub use searcher::{FedMatches, Searcher};
+#[doc(hidden)]
+pub use serialize::StaticRegex;
+#[cfg(feature = "std")]
+pub use set::{RegexSet, SetMatches};
+#[cfg(feature = "std")]
+pub use split::{Segment, Segments, Split, SplitN};
+#[cfg(feature = "futures")]
+pub use stream::{AsyncReaderMatches, MatchesStream};
+#[cfg(feature = "std")]
+pub use transcode::{CharMatches, Utf16Matches};
+pub use tree::ChunkTree;
+
+#[cfg(feature = "std")]
+mod captures;
+mod chunks;
+mod cursor;
+mod dfa;
+#[cfg(feature = "encoding_rs")]
+mod encoding;
+mod fallible;
+mod input;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod look;
+#[cfg(feature = "std")]
+mod nfa;
+#[cfg(feature = "std")]
+mod owned;
+#[cfg(feature = "std")]
+mod pikevm;
+#[cfg(feature = "std")]
+mod position;
+#[cfg(feature = "std")]
+mod replace;
+#[cfg(feature = "std")]
+mod searcher;
+mod serialize;
+#[cfg(feature = "std")]
+mod set;
+#[cfg(feature = "std")]
+mod split;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "std")]
+mod transcode;
+mod tree;
+
+#[cfg(feature = "std")]
+type Dfa = DenseDFA<Vec<usize>, usize>;
+/// A dense DFA searched in place, see [Regex::from_bytes_unchecked].
+type StaticDfa = DenseDFA<&'static [usize], usize>;
+#[cfg(feature = "alloc")]
+use serialize::AlignedDfa;
+
+/// Above this estimated number of states, [DfaKind::Auto] uses a lazy DFA.
+#[cfg(feature = "std")]
+const DENSE_STATES_ESTIMATE_LIMIT: u64 = 10_000;
+
+/// The default of [RegexBuilder::lazy_cache_size].
+#[cfg(feature = "std")]
+const DEFAULT_LAZY_CACHE_SIZE: usize = 2 * (1 << 20);
+
+/// The default of [Matches::window_limit].
+#[cfg(feature = "std")]
+const DEFAULT_WINDOW_LIMIT: usize = 1 << 20;
+
+/// A regular expression.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    fw: Automata,
+    bw: Automata,
+    #[cfg(feature = "std")]
+    nfa: Nfa,
+    /// Match what precedes a position in a match, read backwards from there,
+    /// for searching forwards and backwards respectively, see [dfa::Live].
+    #[cfg(feature = "std")]
+    fw_live: Nfa,
+    #[cfg(feature = "std")]
+    bw_live: Nfa,
+    /// The number of bytes the cache of the lazy DFA for `fw_live` or `bw_live` may use.
+    #[cfg(feature = "std")]
+    live_cache_size: usize,
+    kind: MatchKind,
+}
+
+/// An error that occurred while building a [Regex] or [RegexSet].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    /// The regex could not be parsed.
+    #[cfg(feature = "std")]
+    Syntax(Box<regex_syntax::Error>),
+    /// The regex could not be compiled to a DFA, e.g. because it uses unsupported features.
+    #[cfg(feature = "std")]
+    Automata(regex_automata::Error),
+    /// The regex uses a feature which is not supported here.
+    #[cfg(feature = "std")]
+    Unsupported(String),
+    /// The bytes passed to `Regex::from_bytes` or [Regex::from_bytes_unchecked] are not a valid serialized regex.
+    Deserialize {
+        /// What is wrong with the bytes.
+        message: &'static str,
+        /// The offset in the bytes at which the problem was found.
+        offset: usize,
+    },
+    /// An automaton would take up more memory than allowed,
+    /// see [RegexBuilder::size_limit] and [RegexBuilder::dfa_size_limit].
+    SizeLimitExceeded {
+        /// The limit which was exceeded, in bytes.
+        limit: usize,
+    },
+}
+
+/// The automata matching the regex in one direction.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+enum Automata {
+    /// DFAs, used unless the regex needs look-around.
+    #[cfg(feature = "std")]
+    Dfa {
+        /// Finds the end of the leftmost-first match, wherever it starts.
+        unanchored: Dfa,
+        /// Finds the longest match starting at the beginning of the input.
+        anchored: Dfa,
+    },
+    /// Like [Automata::Dfa], but with narrower state IDs or sparse transitions,
+    /// see [RegexBuilder::sparse] and [RegexBuilder::state_id_width].
+    #[cfg(feature = "std")]
+    Compact {
+        unanchored: CompactDfa,
+        anchored: CompactDfa,
+    },
+    /// Like [Automata::Dfa], but the DFAs refer to serialized bytes.
+    StaticDfa {
+        unanchored: StaticDfa,
+        anchored: StaticDfa,
+    },
+    /// Like [Automata::StaticDfa], but the bytes were copied when loading the regex.
+    #[cfg(feature = "alloc")]
+    AlignedDfa {
+        unanchored: AlignedDfa,
+        anchored: AlignedDfa,
+    },
+    /// A DFA determinized lazily while searching, used instead of DFAs which would be too large.
+    #[cfg(feature = "std")]
+    Lazy {
+        nfa: Nfa,
+        /// The number of bytes the cache of each lazy DFA may use.
+        cache_size: usize,
+    },
+    /// An NFA simulated by the Pike VM, which finds whole matches in a single pass,
+    /// used if the regex needs look-around.
+    /// A lazy DFA for the NFA without its assertions finds where matches might be first,
+    /// so the Pike VM only runs on those parts of the haystack.
+    #[cfg(feature = "std")]
+    Nfa { nfa: Nfa, relaxed: Nfa },
+}
+
+#[cfg(feature = "std")]
+impl Automata {
+    fn memory_usage(&self) -> usize {
+        match self {
+            Automata::Dfa {
+                unanchored,
+                anchored,
+            } => unanchored.memory_usage() + anchored.memory_usage(),
+            Automata::Compact {
+                unanchored,
+                anchored,
+            } => unanchored.memory_usage() + anchored.memory_usage(),
+            Automata::StaticDfa { .. } => 0,
+            Automata::AlignedDfa {
+                unanchored,
+                anchored,
+            } => unanchored.memory_usage() + anchored.memory_usage(),
+            Automata::Lazy { nfa, .. } => nfa.memory_usage(),
+            Automata::Nfa { nfa, relaxed } => nfa.memory_usage() + relaxed.memory_usage(),
+        }
+    }
+
+    /// Builds the automata searching with the Pike VM for a regex with look-around.
+    fn look_around(nfa: Nfa) -> Automata {
+        let relaxed = nfa.without_looks();
+        Automata::Nfa { nfa, relaxed }
+    }
+
+    /// Returns the NFAs the automata are made of, if they weren't built into DFAs.
+    fn nfas(&self) -> impl Iterator<Item = &Nfa> {
+        let nfas = match self {
+            Automata::Lazy { nfa, .. } => [Some(nfa), None],
+            Automata::Nfa { nfa, relaxed } => [Some(nfa), Some(relaxed)],
+            _ => [None, None],
+        };
+        nfas.into_iter().flatten()
+    }
+}
+
+/// The state of the automata used by a single search.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Caches {
+    /// DFAs built upfront don't need any.
+    None,
+    #[cfg(feature = "std")]
+    Lazy {
+        unanchored: lazy::Cache,
+        anchored: lazy::Cache,
+        rev: lazy::Cache,
+    },
+    #[cfg(feature = "std")]
+    Nfa(pikevm::Cache),
+    /// The lazy DFA finding where matches with look-around might be, and the Pike VM checking them.
+    #[cfg(feature = "std")]
+    Look {
+        relaxed: lazy::Cache,
+        nfa: pikevm::Cache,
+    },
+}
+
+/// A builder for a regex from a string.
+/// This allows several configuration options, such as unicode support and case sensitivity.
+/// For basically all of these options, see [regex_automata::dense::Builder].
+///
+/// ```rust
+/// use hotsauce::RegexBuilder;
+///
+/// let regex = RegexBuilder::new()
+///     .case_insensitive(true)
+///     .build("hello")
+///     .unwrap();
+/// let mat = regex.matches("HeLlO".bytes()).next();
+/// assert_eq!(Some(0..5), mat);
+/// ````
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct RegexBuilder {
+    parser: ParserBuilder,
+    dfa: dense::Builder,
+    kind: MatchKind,
+    crlf: bool,
+    dfa_kind: DfaKind,
+    sparse: bool,
+    state_id_width: StateIdWidth,
+    lazy_cache_size: usize,
+    size_limit: usize,
+    dfa_size_limit: usize,
+}
+
+/// The semantics used to pick a match among all matches starting at the same position.
+///
+/// Matches are always leftmost, i.e. the match starting first is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchKind {
+    /// Prefer the match found by the earliest alternative, like the `regex` crate does.
+    /// For example `a|abc` matches `a` in `abc`.
+    #[default]
+    LeftmostFirst,
+    /// Prefer the longest match, like POSIX does.
+    /// For example `a|abc` matches `abc` in `abc`.
+    LeftmostLongest,
+}
+
+/// The kind of DFA used to search, see [RegexBuilder::dfa_kind].
+/// Regexes with anchors or word boundaries are always searched with an NFA instead.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DfaKind {
+    /// Build dense DFAs, unless they are estimated to get too large, then use a lazy DFA.
+    #[default]
+    Auto,
+    /// Always build dense DFAs upfront.
+    /// These are the fastest to search with, but building them may take exponential time and memory.
+    Dense,
+    /// Build DFA states lazily while searching, keeping them in a cache of bounded size.
+    /// If the cache has to be cleared too often, the search falls back to simulating the NFA.
+    Lazy,
+}
+
+/// The type of the state IDs in DFAs, see [RegexBuilder::state_id_width].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum StateIdWidth {
+    /// `u8`, for DFAs with at most 256 states.
+    U8,
+    /// `u16`, for DFAs with up to 65536 states.
+    U16,
+    /// `u32`.
+    U32,
+    /// `usize`, which is the fastest to search with.
+    #[default]
+    Usize,
+}
+
+/// An iterator over the (non-overlapping) matches.
+///
+/// This runs in time linear in the length of the haystack.
+/// Bytes are only buffered while a match might still start in them, up to [Matches::window_limit]:
+/// `a[^z]*z` buffers everything after an `a` until a `z` follows.
+/// Past the limit, the search goes on with an NFA which keeps no bytes, but is a lot slower.
+/// Like in the `regex` crate, an empty match right where the previous match ends is skipped.
+///
+/// Offsets are `usize`, so a haystack longer than that panics on 32-bit targets.
+/// Use [Regex::matches_from] for `u64` offsets.
+#[derive(Debug)]
+pub struct Matches<'r, Haystack: Iterator<Item = u8>> {
+    input: Input<Haystack>,
+    /// The automata running in the direction of the search.
+    dfa: &'r Automata,
+    /// The automata running in the opposite direction, used to find where a match starts.
+    rev: &'r Automata,
+    /// Tells which bytes no match can start in anymore, see [dfa::Live].
+    #[cfg(feature = "std")]
+    live: &'r Nfa,
+    /// The cache of the lazy DFA for `live`, created once it is needed.
+    #[cfg(feature = "std")]
+    live_cache: Option<lazy::Cache>,
+    #[cfg(feature = "std")]
+    live_cache_size: usize,
+    /// The NFA to go on with once a match gets longer than the input keeps,
+    /// see [Input::set_max_len], together with its cache.
+    /// Backward searches only have one if the regex has no DFAs for them.
+    #[cfg(feature = "std")]
+    too_long: (Option<&'r Nfa>, Option<pikevm::Cache>),
+    kind: MatchKind,
+    caches: Caches,
+    /// Where the search left off when the bytes of a partial input ran out.
+    progress: Option<Progress>,
+    needs_advance: bool,
+    /// The end of the last match, an empty match there is skipped like the `regex` crate does.
+    last_end: Option<u64>,
+    /// Whether to stop whenever the bytes read so far can't be part of a match,
+    /// so they can be inspected before they are dropped.
+    stop_when_skipped: bool,
+    /// Whether the search stopped because a match didn't fit in the window, see [Matches::is_truncated].
+    truncated: bool,
+}
+
+/// An iterator over the (non-overlapping) matches, with `u64` offsets from a base,
+/// see [Regex::matches_from].
+#[derive(Debug)]
+pub struct MatchesFrom<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+}
+
+/// An iterator over the (non-overlapping) matches of a backwards search, from last to first,
+/// with indices counted from the start of the haystack, see [Regex::rmatches_exact].
+#[derive(Debug)]
+pub struct RMatches<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    /// The length of the whole haystack.
+    len: usize,
+}
+
+/// The outcome of a single step of searching.
+enum Step {
+    /// A match was found, its bytes are the last ones in the window.
+    Match(Range<u64>),
+    /// None of the bytes in the window can be part of a match.
+    Skipped,
+    /// The haystack is exhausted, or the bytes of a partial input ran out, see [Progress].
+    Done,
+}
+
+/// How far a search got before the bytes of a partial input ran out,
+/// so it can go on once more arrive instead of starting over.
+#[derive(Debug, Clone)]
+enum Progress {
+    /// Finding the end of the leftmost-first match, with the unanchored DFA in `state`.
+    End {
+        search_start: u64,
+        state: usize,
+        end: Option<u64>,
+        /// The length of the window at which to check which bytes can be dropped.
+        limit: u64,
+    },
+    /// Extending the match `start..end` to the longest one, with the anchored DFA in `state`.
+    Extend {
+        search_start: u64,
+        state: usize,
+        start: u64,
+        end: u64,
+    },
+    /// Running the Pike VM, whose threads are kept in its cache, with the best match so far.
+    /// It stops once no match can start up to `until`, see [pikevm::find].
+    #[cfg(feature = "std")]
+    Nfa {
+        mat: Option<(usize, Range<u64>)>,
+        until: Option<u64>,
+    },
+    /// Like [Progress::Nfa], for a match which got too long for the DFAs, see [Stop::TooLong].
+    #[cfg(feature = "std")]
+    TooLong { mat: Option<(usize, Range<u64>)> },
+}
+
+impl Regex {
+    /// Build a new regex from the given string with default settings (see [RegexBuilder]).
+    /// This uses `regex-syntax`, see that for more documentation.
+    #[cfg(feature = "std")]
+    pub fn new(re: &str) -> Result<Regex, Error> {
+        RegexBuilder::new().build(re)
+    }
+
+    /// Returns an iterator over the matches.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("hey").unwrap();
+    /// let mat = regex.matches("abc hey".bytes()).next();
+    /// assert_eq!(Some(4..7), mat);
+    /// ```
+    pub fn matches<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+    ) -> Matches<'_, Haystack> {
+        Matches::new(self, false, haystack, 0)
+    }
+
+    /// Returns the first match starting at or after the cursor's position.
+    /// If there is none and `wrap` is set, the search wraps around to the start of the haystack.
+    /// The cursor is left where it was.
+    ///
+    /// Bytes before the cursor are looked at for look-around assertions,
+    /// so searching from the middle of the haystack finds the same matches as searching it whole would at that position.
+    ///
+    /// ```rust
+    /// use hotsauce::{Regex, SliceCursor};
+    ///
+    /// let regex = Regex::new(r"\bhey").unwrap();
+    /// let mut cursor = SliceCursor::new(b"hey they hey", 1);
+    /// assert_eq!(Some(9..12), regex.find_next(&mut cursor, false));
+    /// ```
+    pub fn find_next(&self, cursor: &mut impl Cursor, wrap: bool) -> Option<Range<u64>> {
+        cursor::find_next(self, cursor, wrap)
+    }
+
+    /// Returns the nearest match starting before the cursor's position.
+    /// If there is none and `wrap` is set, the search wraps around to the end of the haystack.
+    /// The cursor is left where it was.
+    ///
+    /// A match the cursor is inside of is returned whole, extending past the cursor.
+    /// It is found by searching forwards from the nearest match which ends before the cursor,
+    /// or from the start of the haystack if there is none.
+    ///
+    /// ```rust
+    /// use hotsauce::{Regex, SliceCursor};
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mut cursor = SliceCursor::new(b"12 345 6", 5);
+    /// assert_eq!(Some(3..6), regex.find_prev(&mut cursor, false));
+    /// ```
+    pub fn find_prev(&self, cursor: &mut impl Cursor, wrap: bool) -> Option<Range<u64>> {
+        cursor::find_prev(self, cursor, wrap)
+    }
+
+    /// Returns an iterator over the matches, with `u64` offsets starting at `base`.
+    /// This is meant for haystacks which don't start at the beginning of a file,
+    /// or are longer than `usize` can count on 32-bit targets.
+    /// Look-around assertions treat `base` as the start of the haystack.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("hey").unwrap();
+    /// let mat = regex.matches_from("abc hey".bytes(), 1 << 40).next();
+    /// assert_eq!(Some((1 << 40) + 4..(1 << 40) + 7), mat);
+    /// ```
+    pub fn matches_from<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+        base: u64,
+    ) -> MatchesFrom<'_, Haystack> {
+        MatchesFrom {
+            matches: Matches::new(self, false, haystack, base),
+        }
+    }
+
+    /// Returns an iterator over the matches, searching backwards.
+    /// The iterator needs to go backwards.
+    /// The matches returned will be indices into the iterator, see the example.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("hey").unwrap();
+    /// let mat = regex.rmatches("hey abc".bytes().rev()).next();
+    /// assert_eq!(Some(4..7), mat);
+    /// ```
+    pub fn rmatches<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+    ) -> Matches<'_, Haystack> {
+        Matches::new(self, true, haystack, 0)
+    }
+
+    /// Returns an iterator over the matches from last to first, searching backwards.
+    /// Unlike [Regex::rmatches], the haystack goes forwards and the matches are indices into it.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("hey").unwrap();
+    /// let mat = regex.rmatches_exact("hey abc".bytes()).next();
+    /// assert_eq!(Some(0..3), mat);
+    /// ```
+    pub fn rmatches_exact<Haystack>(&self, haystack: Haystack) -> RMatches<'_, Rev<Haystack>>
+    where
+        Haystack: DoubleEndedIterator<Item = u8> + ExactSizeIterator,
+    {
+        let len = haystack.len();
+        self.rmatches_with_len(haystack.rev(), len)
+    }
+
+    /// Like [Regex::rmatches_exact], for a haystack which already goes backwards and is `len` bytes long.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the haystack turns out to be longer than `len`.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("hey").unwrap();
+    /// let hay = "hey abc";
+    /// let mat = regex.rmatches_with_len(hay.bytes().rev(), hay.len()).next();
+    /// assert_eq!(Some(0..3), mat);
+    /// ```
+    pub fn rmatches_with_len<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+        len: usize,
+    ) -> RMatches<'_, Haystack> {
+        RMatches {
+            matches: self.rmatches(haystack),
+            len,
+        }
+    }
+
+    /// Returns an iterator over the matches in a haystack whose bytes can fail to be read,
+    /// like the ones of [std::io::Read::bytes].
+    /// The first error is returned once the matches before it were,
+    /// and a match interrupted by it is dropped.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let hay = [Ok(b'1'), Ok(b' '), Ok(b'2'), Err("disconnected"), Ok(b'3')];
+    /// let mut matches = regex.try_matches(hay.into_iter());
+    /// assert_eq!(Some(Ok(0..1)), matches.next());
+    /// assert_eq!(Some(Err("disconnected")), matches.next());
+    /// assert_eq!(None, matches.next());
+    /// ```
+    pub fn try_matches<Haystack: Iterator<Item = Result<u8, E>>, E>(
+        &self,
+        haystack: Haystack,
+    ) -> TryMatches<'_, Haystack, E> {
+        TryMatches::new(self.matches(fallible::Fallible::new(haystack)))
+    }
+
+    /// Returns an iterator over the matches in a haystack made of chunks,
+    /// with offsets into the chunks joined together.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mat = regex.matches_chunks(["ab 1", "23 c"]).next();
+    /// assert_eq!(Some(3..6), mat);
+    /// ```
+    pub fn matches_chunks<I>(&self, chunks: I) -> Matches<'_, Chunks<I::IntoIter>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        self.matches_in_chunks(Chunks::new(chunks.into_iter()))
+    }
+
+    /// Like [Regex::matches], reading a chunk at a time instead of byte by byte.
+    fn matches_in_chunks<I>(&self, chunks: Chunks<I>) -> Matches<'_, Chunks<I>>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let matches = self.matches(chunks);
+        #[cfg(feature = "std")]
+        let matches = {
+            let mut matches = matches;
+            matches.input.set_fill(Chunks::fill);
+            matches
+        };
+        matches
+    }
+
+    /// Returns an iterator over the matches in a haystack of characters, with ranges of character indices.
+    /// The characters are encoded to UTF-8 as they are searched.
+    /// Matches which would split a character, like empty matches between its bytes, are skipped.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("wörld").unwrap();
+    /// let mat = regex.matches_chars("héllo wörld".chars()).next();
+    /// assert_eq!(Some(6..11), mat);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn matches_chars<I: Iterator<Item = char>>(&self, chars: I) -> CharMatches<'_, I> {
+        CharMatches::new(self.matches(transcode::Utf8::new(chars)))
+    }
+
+    /// Returns an iterator over the matches in a haystack of UTF-16 code units,
+    /// with ranges of code unit indices.
+    /// Unpaired surrogates are searched as U+FFFD, like [String::from_utf16_lossy] does,
+    /// otherwise this works like [Regex::matches_chars].
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("wörld").unwrap();
+    /// let hay = "🌶 wörld".encode_utf16().collect::<Vec<_>>();
+    /// let mat = regex.matches_utf16(hay.into_iter()).next();
+    /// assert_eq!(Some(3..8), mat);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn matches_utf16<I: Iterator<Item = u16>>(&self, units: I) -> Utf16Matches<'_, I> {
+        let chars = transcode::utf16_chars(units);
+        Utf16Matches::new(self.matches(transcode::Utf8::new(chars)))
+    }
+
+    /// Returns an iterator over the matches in a haystack in the given encoding,
+    /// with ranges of the encoded bytes.
+    /// A BOM overrides the encoding, so UTF-8 and UTF-16 haystacks are detected.
+    /// The haystack is decoded to UTF-8 as it is searched, with malformed sequences replaced by U+FFFD,
+    /// and matches which would split a character are skipped.
+    ///
+    /// ```rust
+    /// use encoding_rs::WINDOWS_1252;
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("wörld").unwrap();
+    /// let mat = regex.matches_encoded(b"hello w\xf6rld".iter().copied(), WINDOWS_1252).next();
+    /// assert_eq!(Some(6..11), mat);
+    /// ```
+    #[cfg(feature = "encoding_rs")]
+    pub fn matches_encoded<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+        encoding: &'static encoding_rs::Encoding,
+    ) -> EncodedMatches<'_, Haystack> {
+        EncodedMatches::new(self.matches(encoding::Decoded::new(haystack, encoding)))
+    }
+
+    /// Returns an iterator over the matches in a haystack stored in chunks, like a rope.
+    /// With the `ropey` feature, this works with `ropey::Rope` and `ropey::RopeSlice`,
+    /// with offsets in bytes from the start of the rope or slice.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mat = regex.matches_tree(&["ab 1", "23 c"][..]).next();
+    /// assert_eq!(Some(3..6), mat);
+    /// ```
+    pub fn matches_tree<'t, T: ChunkTree + ?Sized>(
+        &self,
+        tree: &'t T,
+    ) -> Matches<'_, Chunks<T::Forward<'t>>> {
+        self.matches_in_chunks(Chunks::new(tree.chunks()))
+    }
+
+    /// Like [Regex::matches_tree], searching backwards and returning the matches from last to first,
+    /// see [Regex::rmatches_exact].
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mat = regex.rmatches_tree(&["ab 1", "23 c 4"][..]).next();
+    /// assert_eq!(Some(9..10), mat);
+    /// ```
+    pub fn rmatches_tree<'t, T: ChunkTree + ?Sized>(
+        &self,
+        tree: &'t T,
+    ) -> RMatches<'_, RevChunks<T::Backward<'t>>> {
+        self.rmatches_with_len(RevChunks::new(tree.rev_chunks()), tree.len())
+    }
+
+    /// Returns an iterator over the matches in the bytes of a reader.
+    /// The bytes are read a buffer at a time, instead of one by one like [std::io::Read::bytes] does.
+    ///
+    /// ```rust
+    /// use std::io::Cursor;
+    ///
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mat = regex.matches_reader(Cursor::new("ab 123 c")).next();
+    /// assert_eq!(Some(3..6), mat.transpose().unwrap());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn matches_reader<R: std::io::BufRead>(&self, reader: R) -> ReaderMatches<'_, R> {
+        self.matches_reader_from(reader, 0)
+    }
+
+    /// Like [Regex::matches_reader], with offsets starting at `base`, see [Regex::matches_from].
+    /// Useful for a reader which was seeked to `base` first.
+    #[cfg(feature = "std")]
+    pub fn matches_reader_from<R: std::io::BufRead>(
+        &self,
+        reader: R,
+        base: u64,
+    ) -> ReaderMatches<'_, R> {
+        let haystack = chunks::ReaderBytes::new(reader);
+        let mut matches = Matches::new(self, false, haystack, base);
+        matches.input.set_fill(chunks::ReaderBytes::fill);
+        ReaderMatches::new(matches)
+    }
+
+    /// Returns a stream of the matches in a stream of bytes, with the offsets of [Searcher].
+    /// Streams which aren't [Unpin] can be pinned with [Box::pin] or [std::pin::pin].
+    ///
+    /// ```rust
+    /// use futures::{executor::block_on, stream, StreamExt};
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mut matches = regex.matches_stream(stream::iter("ab 123 c".bytes()));
+    /// assert_eq!(Some(3..6), block_on(matches.next()));
+    /// ```
+    #[cfg(feature = "futures")]
+    pub fn matches_stream<S>(&self, stream: S) -> MatchesStream<'_, S>
+    where
+        S: futures_core::Stream<Item = u8> + Unpin,
+    {
+        MatchesStream::new(self.searcher(), stream)
+    }
+
+    /// Returns a stream of the matches in an asynchronous reader, with the offsets of [Searcher].
+    /// The bytes are searched a buffer at a time.
+    ///
+    /// ```rust
+    /// use futures::{executor::block_on, io::Cursor, StreamExt};
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[0-9]+").unwrap();
+    /// let mut matches = regex.matches_async_reader(Cursor::new("ab 123 c"));
+    /// assert_eq!(Some(3..6), block_on(matches.next()).transpose().unwrap());
+    /// ```
+    #[cfg(feature = "futures")]
+    pub fn matches_async_reader<R>(&self, reader: R) -> AsyncReaderMatches<'_, R>
+    where
+        R: futures_io::AsyncBufRead + Unpin,
+    {
+        AsyncReaderMatches::new(self.searcher(), reader)
+    }
+
+    /// Returns a searcher which is fed the haystack in chunks, instead of pulling it from an iterator.
+    /// It finds the same matches as [Regex::matches], see [Searcher].
+    #[cfg(feature = "std")]
+    pub fn searcher(&self) -> Searcher<'_> {
+        self.searcher_from(0)
+    }
+
+    /// Like [Regex::searcher], with offsets starting at `base`, see [Regex::matches_from].
+    #[cfg(feature = "std")]
+    pub fn searcher_from(&self, base: u64) -> Searcher<'_> {
+        let haystack = searcher::Fed::default();
+        Searcher::new(Matches::new(self, false, haystack, base))
+    }
+
+    /// Returns an iterator over the matches, including the positions of their capture groups.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new(r"(?P<key>\w+)=(\w+)").unwrap();
+    /// let caps = regex.captures("abc key=value".bytes()).next().unwrap();
+    /// assert_eq!(Some(4..13), caps.get(0));
+    /// assert_eq!(Some(4..7), caps.name("key"));
+    /// assert_eq!(Some(8..13), caps.get(2));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn captures<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+    ) -> CaptureMatches<'_, Haystack> {
+        CaptureMatches::new(self.matches(haystack), &self.nfa)
+    }
+
+    /// Returns an iterator over the bytes of the haystack, with every match replaced.
+    /// The haystack is consumed lazily, only bytes which might be part of a match are buffered.
+    /// See [Replacer] for the kinds of replacements.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new(r"(?P<user>\w+)@example\.com").unwrap();
+    /// let out = regex
+    ///     .replace_all("mail alice@example.com".bytes(), "<$user>")
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(b"mail <alice>".to_vec(), out);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn replace_all<Haystack: Iterator<Item = u8>, R: Replacer>(
+        &self,
+        haystack: Haystack,
+        replacer: R,
+    ) -> ReplaceAll<'_, Haystack, R> {
+        ReplaceAll::new(self.matches(haystack), &self.nfa, replacer)
+    }
+
+    /// Returns an iterator over the parts of the haystack between matches.
+    /// This always yields at least one (possibly empty) part.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new(r",\s*").unwrap();
+    /// let parts = regex.split("a, b,,c".bytes()).collect::<Vec<_>>();
+    /// assert_eq!(vec![0..1, 3..4, 5..5, 6..7], parts);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn split<Haystack: Iterator<Item = u8>>(&self, haystack: Haystack) -> Split<'_, Haystack> {
+        Split::new(self.matches(haystack))
+    }
+
+    /// Returns an iterator over at most `limit` parts of the haystack between matches.
+    /// The last part is the rest of the haystack, which is searched no further.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new(",").unwrap();
+    /// let parts = regex.splitn("a,b,c".bytes(), 2).collect::<Vec<_>>();
+    /// assert_eq!(vec![0..1, 2..5], parts);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn splitn<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+        limit: usize,
+    ) -> SplitN<'_, Haystack> {
+        SplitN::new(self.split(haystack), limit)
+    }
+
+    /// Returns an iterator over the matches and the gaps between them, covering the whole haystack.
+    /// Gaps are never empty.
+    ///
+    /// ```rust
+    /// use hotsauce::{Regex, Segment};
+    ///
+    /// let regex = Regex::new(r"\d+").unwrap();
+    /// let segments = regex.segments("ab12c".bytes()).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     vec![Segment::Gap(0..2), Segment::Match(2..4), Segment::Gap(4..5)],
+    ///     segments,
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn segments<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+    ) -> Segments<'_, Haystack> {
+        Segments::new(self.matches(haystack))
+    }
+
+    /// Returns the number of capture groups, including the implicit group 0 for the whole match.
+    #[cfg(feature = "std")]
+    pub fn captures_len(&self) -> usize {
+        self.nfa.names().len()
+    }
+
+    /// Returns the number of bytes the automata in both directions use on the heap,
+    /// including the NFA resolving capture groups.
+    /// Caches created while searching are not included,
+    /// but the ones of lazy DFAs fit within [RegexBuilder::dfa_size_limit] together with the DFAs.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[a-z]+").unwrap();
+    /// assert!(regex.memory_usage() > 0);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn memory_usage(&self) -> usize {
+        self.fw.memory_usage()
+            + self.bw.memory_usage()
+            + self.nfa.memory_usage()
+            + self.fw_live.memory_usage()
+            + self.bw_live.memory_usage()
+    }
+
+    /// Returns the names of the capture groups, in order of their index.
+    /// Unnamed groups, like the implicit group 0, have no name.
+    #[cfg(feature = "std")]
+    pub fn capture_names(&self) -> impl Iterator<Item = Option<&str>> {
+        self.nfa.names().iter().map(|name| name.as_deref())
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&str> for Regex {
+    type Error = Error;
+
+    fn try_from(str: &str) -> Result<Self, Self::Error> {
+        Regex::new(str)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Syntax(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Automata(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Unsupported(msg) => write!(f, "unsupported regex feature: {}", msg),
+            Error::Deserialize { message, offset } => write!(
+                f,
+                "failed to deserialize regex: {} at offset {}",
+                message, offset
+            ),
+            Error::SizeLimitExceeded { limit } => {
+                write!(f, "compiled regex exceeds size limit of {} bytes", limit)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Syntax(err) => Some(&**err),
+            Error::Automata(err) => Some(err),
+            Error::Unsupported(_) | Error::Deserialize { .. } | Error::SizeLimitExceeded { .. } => {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<regex_syntax::Error> for Error {
+    fn from(err: regex_syntax::Error) -> Self {
+        Error::Syntax(Box::new(err))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<regex_automata::Error> for Error {
+    fn from(err: regex_automata::Error) -> Self {
+        Error::Automata(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl RegexBuilder {
+    /// Create a new [Regex] builder.
+    pub fn new() -> RegexBuilder {
+        RegexBuilder {
+            parser: ParserBuilder::new(),
+            dfa: dense::Builder::new(),
+            kind: MatchKind::default(),
+            crlf: false,
+            dfa_kind: DfaKind::default(),
+            sparse: false,
+            state_id_width: StateIdWidth::default(),
+            lazy_cache_size: DEFAULT_LAZY_CACHE_SIZE,
+            size_limit: 10 * (1 << 20),
+            dfa_size_limit: 10 * (1 << 20),
+        }
+    }
+
+    /// Build the regex with the given expression.
+    pub fn build(&self, re: &str) -> Result<Regex, Error> {
+        let hir = self.parser.build().parse(re)?;
+
+        let lazy = match self.dfa_kind {
+            DfaKind::Auto => nfa::dfa_states_estimate(&hir) > DENSE_STATES_ESTIMATE_LIMIT,
+            DfaKind::Dense => false,
+            DfaKind::Lazy => true,
+        };
+
+        let (fw, bw) = if nfa::has_look_around(&hir) {
+            // The lazy DFAs without the assertions search together with the one for which bytes
+            // a match can start in.
+            self.check_caches(2)?;
+            (
+                Automata::look_around(self.forward_nfa(slice::from_ref(&hir))?),
+                Automata::look_around(self.reverse_nfa(slice::from_ref(&hir))?),
+            )
+        } else if lazy {
+            self.build_lazy(&hir)?
+        } else {
+            match self.build_dense(re) {
+                Err(Error::SizeLimitExceeded { .. }) if self.dfa_kind == DfaKind::Auto => {
+                    self.build_lazy(&hir)?
+                }
+                dfas => dfas?,
+            }
+        };
+
+        let nfa = Nfa::new(&hir, self.crlf, self.size_limit).map_err(|_| self.nfa_too_big())?;
+        let (fw_live, bw_live) = (nfa.reversed().suffixes(), nfa.suffixes());
+
+        let nfas = [&fw, &bw].into_iter().flat_map(Automata::nfas);
+        self.check_nfas(nfas.chain([&nfa, &fw_live, &bw_live]))?;
+
+        Ok(Regex {
+            fw,
+            bw,
+            fw_live,
+            bw_live,
+            nfa,
+            live_cache_size: self.lazy_cache_size,
+            kind: self.kind,
+        })
+    }
+
+    /// Fails if the NFAs exceed the size limit together.
+    fn check_nfas<'a>(&self, nfas: impl IntoIterator<Item = &'a Nfa>) -> Result<(), Error> {
+        let memory = nfas.into_iter().map(Nfa::memory_usage).sum::<usize>();
+        match memory > self.size_limit {
+            true => Err(self.nfa_too_big()),
+            false => Ok(()),
+        }
+    }
+
+    /// Fails if the caches of the given number of lazy DFAs can exceed the DFA size limit.
+    fn check_caches(&self, caches: usize) -> Result<(), Error> {
+        match self.lazy_cache_size.saturating_mul(caches) > self.dfa_size_limit {
+            true => Err(Error::SizeLimitExceeded {
+                limit: self.dfa_size_limit,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    fn build_lazy(&self, hir: &Hir) -> Result<(Automata, Automata), Error> {
+        // Three lazy DFAs search together with the one for which bytes a match can start in.
+        self.check_caches(4)?;
+
+        Ok((
+            Automata::Lazy {
+                nfa: self.forward_nfa(slice::from_ref(hir))?,
+                cache_size: self.lazy_cache_size,
+            },
+            Automata::Lazy {
+                nfa: self.reverse_nfa(slice::from_ref(hir))?,
+                cache_size: self.lazy_cache_size,
+            },
+        ))
+    }
+
+    /// Builds the dense DFAs, failing once the ones built so far exceed the DFA size limit together
+    /// with the cache of the lazy DFA for which bytes a match can start in.
+    /// If compact DFAs are configured, only their size counts towards the limit.
+    fn build_dense(&self, re: &str) -> Result<(Automata, Automata), Error> {
+        let compact = self.sparse || self.state_id_width != StateIdWidth::Usize;
+
+        self.check_caches(1)?;
+        let memory = Cell::new(self.lazy_cache_size);
+        let track = |bytes: usize| {
+            memory.set(memory.get() + bytes);
+            if memory.get() > self.dfa_size_limit {
+                return Err(Error::SizeLimitExceeded {
+                    limit: self.dfa_size_limit,
+                });
+            }
+            Ok(())
+        };
+        let build = |builder: &dense::Builder| {
+            let dfa = builder.build(re)?;
+            if !compact {
+                track(dfa.memory_usage())?;
+            }
+            Ok::<_, Error>(dfa)
+        };
+        let build_compact = |builder: &dense::Builder| {
+            let dfa = CompactDfa::new(&build(builder)?, self.sparse, self.state_id_width)?;
+            track(dfa.memory_usage())?;
+            Ok::<_, Error>(dfa)
+        };
+
+        // The `(?s:.)*?` prefix of an unanchored DFA has to skip any byte, not just valid UTF-8,
+        // or the search ends at the first invalid byte.
+        let mut unanchored = self.dfa.clone();
+        unanchored
+            .anchored(false)
+            .longest_match(false)
+            .allow_invalid_utf8(true);
+        let mut anchored = self.dfa.clone();
+        anchored.anchored(true).longest_match(true);
+
+        if compact {
+            return Ok((
+                Automata::Compact {
+                    unanchored: build_compact(&unanchored)?,
+                    anchored: build_compact(&anchored)?,
+                },
+                Automata::Compact {
+                    unanchored: build_compact(unanchored.reverse(true))?,
+                    anchored: build_compact(anchored.reverse(true))?,
+                },
+            ));
+        }
+
+        Ok((
+            Automata::Dfa {
+                unanchored: build(&unanchored)?,
+                anchored: build(&anchored)?,
+            },
+            Automata::Dfa {
+                unanchored: build(unanchored.reverse(true))?,
+                anchored: build(anchored.reverse(true))?,
+            },
+        ))
+    }
+
+    fn forward_nfa(&self, hirs: &[Hir]) -> Result<Nfa, Error> {
+        Nfa::new_many(hirs, self.crlf, self.size_limit).map_err(|_| self.nfa_too_big())
+    }
+
+    fn reverse_nfa(&self, hirs: &[Hir]) -> Result<Nfa, Error> {
+        Nfa::new_reverse(hirs, self.crlf, self.size_limit).map_err(|_| self.nfa_too_big())
+    }
+
+    fn nfa_too_big(&self) -> Error {
+        Error::SizeLimitExceeded {
+            limit: self.size_limit,
+        }
+    }
+
+    /// Build a set of regexes with the given expressions.
+    /// All of them are compiled into a single automaton, which is searched with a lazy DFA
+    /// unless a regex needs look-around.
+    ///
+    /// ```rust
+    /// use hotsauce::RegexBuilder;
+    ///
+    /// let set = RegexBuilder::new()
+    ///     .case_insensitive(true)
+    ///     .build_set(["hello", "world"])
+    ///     .unwrap();
+    /// assert_eq!(vec![0, 1], set.which_match("Hello World".bytes()));
+    /// ```
+    pub fn build_set<I, S>(&self, res: I) -> Result<RegexSet, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let hirs = res
+            .into_iter()
+            .map(|re| Ok(self.parser.build().parse(re.as_ref())?))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let rev = match hirs.iter().any(nfa::has_look_around) {
+            false => Some(self.reverse_nfa(&hirs)?),
+            true => None,
+        };
+        if rev.is_some() {
+            // Three lazy DFAs search together with the one for which bytes a match can start in.
+            self.check_caches(4)?;
+        }
+
+        let set = RegexSet::from_nfa(
+            self.forward_nfa(&hirs)?,
+            rev,
+            self.kind,
+            self.lazy_cache_size,
+        );
+        self.check_nfas(set.nfas())?;
+        Ok(set)
+    }
+
+    /// Enable case insensitivity.
+    /// This is disabled by default.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.case_insensitive(yes);
+        self.dfa.case_insensitive(yes);
+        self
+    }
+
+    /// Allow or disallow the use of whitespace and comments in regex.
+    /// This is disabled by default.
+    pub fn verbose(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.ignore_whitespace(yes);
+        self.dfa.ignore_whitespace(yes);
+        self
+    }
+
+    /// Set whether `^` and `$` match at the start and end of lines, instead of only the haystack.
+    /// Disabled by default.
+    ///
+    /// Like any regex with anchors or word boundaries, one using them is searched with an NFA
+    /// wherever it would match without them. Haystacks with many such places, like every `b`
+    /// for `^b`, are searched several times slower than without anchors.
+    ///
+    /// ```rust
+    /// use hotsauce::RegexBuilder;
+    ///
+    /// let regex = RegexBuilder::new().multi_line(true).build("^b").unwrap();
+    /// let mat = regex.matches("a\nb".bytes()).next();
+    /// assert_eq!(Some(2..3), mat);
+    /// ```
+    pub fn multi_line(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.multi_line(yes);
+        self
+    }
+
+    /// Set whether `\r\n` is treated as a line terminator by `^` and `$` in multi-line mode,
+    /// in addition to `\n`. A lone `\r` is treated as a line terminator as well.
+    /// Disabled by default.
+    pub fn crlf(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.crlf = yes;
+        self
+    }
+
+    /// Set whether dot should match new line characters.
+    /// Disabled by default.
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.dot_matches_new_line(yes);
+        self.dfa.dot_matches_new_line(yes);
+        self
+    }
+
+    /// Set the semantics used to pick between matches starting at the same position.
+    /// Defaults to [MatchKind::LeftmostFirst].
+    ///
+    /// ```rust
+    /// use hotsauce::{MatchKind, RegexBuilder};
+    ///
+    /// let regex = RegexBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostLongest)
+    ///     .build("a|abc")
+    ///     .unwrap();
+    /// let mat = regex.matches("abc".bytes()).next();
+    /// assert_eq!(Some(0..3), mat);
+    /// ```
+    pub fn match_kind(&mut self, kind: MatchKind) -> &mut RegexBuilder {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the kind of DFA used to search.
+    /// Defaults to [DfaKind::Auto].
+    ///
+    /// ```rust
+    /// use hotsauce::{DfaKind, RegexBuilder};
+    ///
+    /// let regex = RegexBuilder::new()
+    ///     .dfa_kind(DfaKind::Lazy)
+    ///     .build("[01]*1[01]{20}")
+    ///     .unwrap();
+    /// let mat = regex.matches("2100000000000000000000".bytes()).next();
+    /// assert_eq!(Some(1..22), mat);
+    /// ```
+    pub fn dfa_kind(&mut self, kind: DfaKind) -> &mut RegexBuilder {
+        self.dfa_kind = kind;
+        self
+    }
+
+    /// Use sparse DFAs, which only store the transitions of each state that don't lead to the dead state.
+    /// They take up a fraction of the memory of dense DFAs for large Unicode classes,
+    /// but are slower to search with.
+    /// Disabled by default.
+    ///
+    /// ```rust
+    /// use hotsauce::{RegexBuilder, StateIdWidth};
+    ///
+    /// let regex = RegexBuilder::new()
+    ///     .sparse(true)
+    ///     .state_id_width(StateIdWidth::U32)
+    ///     .build(r"\w+")
+    ///     .unwrap();
+    /// let mat = regex.matches("¿Qué?".bytes()).next();
+    /// assert_eq!(Some(2..6), mat);
+    /// ```
+    pub fn sparse(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.sparse = yes;
+        self
+    }
+
+    /// Set the type of the state IDs in the DFAs.
+    /// Narrower state IDs make the DFAs smaller,
+    /// but building fails with [Error::Automata] if the DFAs have too many states for them.
+    /// The state IDs of sparse DFAs are offsets into their transitions, which are larger,
+    /// and those of dense DFAs grow with the number of byte classes if
+    /// [RegexBuilder::premultiply] is enabled.
+    /// Defaults to [StateIdWidth::Usize].
+    pub fn state_id_width(&mut self, width: StateIdWidth) -> &mut RegexBuilder {
+        self.state_id_width = width;
+        self
+    }
+
+    /// Set the number of bytes the cache of each lazy DFA may use.
+    /// Three lazy DFAs are used per search, and one more to tell which bytes a match can start in,
+    /// whose caches count towards [RegexBuilder::dfa_size_limit].
+    /// A regex with look-around only uses one, to find where matches might be, together with the latter.
+    /// Defaults to 2 MiB.
+    pub fn lazy_cache_size(&mut self, bytes: usize) -> &mut RegexBuilder {
+        self.lazy_cache_size = bytes;
+        self
+    }
+
+    /// Set the number of bytes the compiled NFAs may use together.
+    /// Building fails with [Error::SizeLimitExceeded] if it's exceeded,
+    /// which happens as soon as a single NFA grows too large, so huge repetitions are cut short.
+    /// Defaults to 10 MiB.
+    ///
+    /// ```rust
+    /// use hotsauce::{Error, RegexBuilder};
+    ///
+    /// let err = RegexBuilder::new().size_limit(1 << 10).build(r"\w{100}").unwrap_err();
+    /// assert!(matches!(err, Error::SizeLimitExceeded { limit: 1024 }));
+    /// ```
+    pub fn size_limit(&mut self, bytes: usize) -> &mut RegexBuilder {
+        self.size_limit = bytes;
+        self
+    }
+
+    /// Set the number of bytes the dense DFAs and the caches of lazy DFAs may use together.
+    /// If the dense DFAs exceed it, [DfaKind::Auto] uses a lazy DFA instead,
+    /// while [DfaKind::Dense] fails with [Error::SizeLimitExceeded], as does a lazy DFA whose caches don't fit.
+    /// Defaults to 10 MiB.
+    ///
+    /// Each DFA is only measured once it is built, so this bounds the memory kept,
+    /// not the time and memory building takes. [DfaKind::Auto] estimates the number of states
+    /// beforehand, so DFAs which blow up aren't built in the first place.
+    pub fn dfa_size_limit(&mut self, bytes: usize) -> &mut RegexBuilder {
+        self.dfa_size_limit = bytes;
+        self
+    }
+
+    /// Enable or disable "swap greed".
+    /// Disabled by default.
+    pub fn swap_greed(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.swap_greed(yes);
+        self.dfa.swap_greed(yes);
+        self
+    }
+
+    /// Enable or disable unicode.
+    /// Enabled by default.
+    pub fn unicode(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.unicode(yes);
+        self.dfa.unicode(yes);
+        self
+    }
+
+    /// Allows the construction of &mut Regex that match invalid UTF-8.
+    pub fn allow_invalid_utf8(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.parser.allow_invalid_utf8(yes);
+        self.dfa.allow_invalid_utf8(yes);
+        self
+    }
+
+    /// Set the nest limit used for the parser.
+    pub fn nest_limit(&mut self, limit: u32) -> &mut RegexBuilder {
+        self.parser.nest_limit(limit);
+        self.dfa.nest_limit(limit);
+        self
+    }
+
+    /// Minimize the DFA to be as small as possible.
+    /// Disabled by default.
+    pub fn minimize(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.dfa.minimize(yes);
+        self
+    }
+
+    /// Premultiply the transition table.
+    /// Enabled by default.
+    pub fn premultiply(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.dfa.premultiply(yes);
+        self
+    }
+
+    /// Shrink the size of the DFA’s alphabet by mapping bytes to their equivalence classes.
+    /// Enabled by default.
+    pub fn byte_classes(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.dfa.byte_classes(yes);
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for RegexBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Matches<'r, Haystack> {
+    /// Creates the iterator, searching the haystack backwards if `reverse` is set.
+    fn new(
+        regex: &'r Regex,
+        reverse: bool,
+        haystack: Haystack,
+        base: u64,
+    ) -> Matches<'r, Haystack> {
+        let (dfa, rev) = match reverse {
+            false => (&regex.fw, &regex.bw),
+            true => (&regex.bw, &regex.fw),
+        };
+
+        #[cfg(feature = "std")]
+        let too_long = match (reverse, dfa) {
+            (false, _) => Some(&regex.nfa),
+            (true, Automata::Lazy { nfa, .. } | Automata::Nfa { nfa, .. }) => Some(nfa),
+            // The NFA resolving capture groups can't be run backwards with the same preferences.
+            (true, _) => None,
+        };
+
+        let input = Input::new(haystack, base);
+        #[cfg(feature = "std")]
+        let input = {
+            let mut input = input;
+            if too_long.is_some() {
+                input.set_max_window(Some(DEFAULT_WINDOW_LIMIT as u64));
+            }
+            input
+        };
+
+        Matches {
+            input,
+            dfa,
+            rev,
+            #[cfg(feature = "std")]
+            live: match reverse {
+                false => &regex.fw_live,
+                true => &regex.bw_live,
+            },
+            #[cfg(feature = "std")]
+            live_cache: None,
+            #[cfg(feature = "std")]
+            live_cache_size: regex.live_cache_size,
+            #[cfg(feature = "std")]
+            too_long: (too_long, None),
+            kind: regex.kind,
+            caches: match (dfa, rev) {
+                #[cfg(feature = "std")]
+                (Automata::Dfa { .. } | Automata::Compact { .. }, _) => Caches::None,
+                (Automata::StaticDfa { .. }, _) => Caches::None,
+                #[cfg(feature = "alloc")]
+                (Automata::AlignedDfa { .. }, _) => Caches::None,
+                #[cfg(feature = "std")]
+                (Automata::Lazy { nfa, cache_size }, Automata::Lazy { nfa: rev_nfa, .. }) => {
+                    Caches::Lazy {
+                        unanchored: lazy::Cache::new(nfa, false, *cache_size),
+                        anchored: lazy::Cache::new(nfa, true, *cache_size),
+                        rev: lazy::Cache::new(rev_nfa, true, *cache_size),
+                    }
+                }
+                #[cfg(feature = "std")]
+                (Automata::Lazy { nfa, .. }, _) => Caches::Nfa(pikevm::Cache::new(nfa)),
+                #[cfg(feature = "std")]
+                (Automata::Nfa { nfa, relaxed }, _) => Caches::Look {
+                    relaxed: lazy::Cache::new(relaxed, false, regex.live_cache_size),
+                    nfa: pikevm::Cache::new(nfa),
+                },
+            },
+            progress: None,
+            needs_advance: false,
+            last_end: None,
+            stop_when_skipped: false,
+            truncated: false,
+        }
+    }
+
+    /// Returns whether the search stopped early, because a match didn't fit in the window.
+    ///
+    /// This only happens without the `std` feature, where the window holds the last 256 bytes read.
+    /// Once a match is found which might start in bytes dropped from it, no more matches are returned,
+    /// as its start isn't known.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Sets how many bytes may be buffered while a match might still start in them, 1 MiB by default.
+    /// Once more would be, the rest of the match is searched for with an NFA,
+    /// which only keeps track of where it is. `None` buffers as many bytes as it takes.
+    /// Iterators looking at the bytes of matches, like [Matches::positions] or [Regex::captures], keep all of them,
+    /// as do backward searches of a regex with DFAs, which has no NFA for them.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("a[^z]*z").unwrap();
+    /// let hay = "a".bytes().chain([b'b'; 100]).chain("z".bytes());
+    /// let mat = regex.matches(hay).window_limit(Some(10)).next();
+    /// assert_eq!(mat, Some(0..102));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn window_limit(mut self, limit: Option<usize>) -> Matches<'r, Haystack> {
+        if self.too_long.0.is_some() {
+            self.input.set_max_window(limit.map(|limit| limit as u64));
+        }
+        self
+    }
+
+    /// Keeps every byte of a match in the window, for wrappers looking at them.
+    #[cfg(feature = "std")]
+    pub(crate) fn keep_match_bytes(&mut self) {
+        self.input.set_max_window(None);
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Matches<'_, Haystack> {
+    /// Searches for the next match, stopping early if `stop_when_skipped` is set.
+    fn step(&mut self) -> Step {
+        if self.truncated {
+            return Step::Done;
+        }
+
+        if self.progress.is_none() {
+            self.input.clear_window();
+
+            if self.needs_advance {
+                // Skip a byte so the empty match isn't found again.
+                if self.input.read_byte().is_none() {
+                    return Step::Done;
+                }
+                self.needs_advance = false;
+            }
+        }
+
+        match self.search() {
+            Ok(mat) => {
+                if mat.is_empty() {
+                    self.needs_advance = true;
+                    if self.last_end == Some(mat.start) {
+                        return Step::Skipped;
+                    }
+                }
+                self.last_end = Some(mat.end);
+                Step::Match(mat)
+            }
+            Err(step) => step,
+        }
+    }
+
+    /// Returns the next match, skipping steps which didn't find one.
+    fn next_match(&mut self) -> Option<Range<u64>> {
+        loop {
+            match self.step() {
+                Step::Match(mat) => return Some(mat),
+                Step::Skipped => {}
+                Step::Done => return None,
+            }
+        }
+    }
+
+    /// Searches for the next match with whichever automata the regex uses.
+    fn search(&mut self) -> Result<Range<u64>, Step> {
+        let progress = self.progress.take();
+        #[cfg(feature = "std")]
+        if let Some(Progress::TooLong { mat }) = progress {
+            return self.search_too_long(Some(Progress::Nfa { mat, until: None }));
+        }
+        #[cfg(feature = "std")]
+        let search_start = match progress {
+            Some(Progress::End { search_start, .. } | Progress::Extend { search_start, .. }) => {
+                search_start
+            }
+            _ => self.input.position(),
+        };
+        let (kind, stop_when_skipped) = (self.kind, self.stop_when_skipped);
+
+        #[cfg(feature = "std")]
+        let live = Some(dfa::Live::new(
+            self.live,
+            &mut self.live_cache,
+            self.live_cache_size,
+        ));
+        #[cfg(not(feature = "std"))]
+        let live = None;
+
+        let result = match (self.dfa, self.rev, &mut self.caches) {
+            #[cfg(feature = "std")]
+            (
+                Automata::Dfa {
+                    unanchored,
+                    anchored,
+                },
+                Automata::Dfa { anchored: rev, .. },
+                _,
+            ) => dfa::search(
+                &mut self.input,
+                unanchored,
+                anchored,
+                rev,
+                live,
+                kind,
+                stop_when_skipped,
+                progress,
+            ),
+            (
+                Automata::StaticDfa {
+                    unanchored,
+                    anchored,
+                },
+                Automata::StaticDfa { anchored: rev, .. },
+                _,
+            ) => dfa::search(
+                &mut self.input,
+                unanchored,
+                anchored,
+                rev,
+                live,
+                kind,
+                stop_when_skipped,
+                progress,
+            ),
+            #[cfg(feature = "alloc")]
+            (
+                Automata::AlignedDfa {
+                    unanchored,
+                    anchored,
+                },
+                Automata::AlignedDfa { anchored: rev, .. },
+                _,
+            ) => dfa::search(
+                &mut self.input,
+                &unanchored.dfa(),
+                &anchored.dfa(),
+                &rev.dfa(),
+                live,
+                kind,
+                stop_when_skipped,
+                progress,
+            ),
+            #[cfg(feature = "std")]
+            (
+                Automata::Compact {
+                    unanchored,
+                    anchored,
+                },
+                Automata::Compact { anchored: rev, .. },
+                _,
+            ) => dfa::search_compact(
+                &mut self.input,
+                unanchored,
+                anchored,
+                rev,
+                live,
+                kind,
+                stop_when_skipped,
+                progress,
+            ),
+            #[cfg(feature = "std")]
+            (
+                Automata::Lazy { nfa, .. },
+                Automata::Lazy { nfa: rev_nfa, .. },
+                Caches::Lazy {
+                    unanchored,
+                    anchored,
+                    rev,
+                },
+            ) => dfa::search(
+                &mut self.input,
+                Lazy::new(nfa, unanchored),
+                Lazy::new(nfa, anchored),
+                Lazy::new(rev_nfa, rev),
+                live,
+                kind,
+                stop_when_skipped,
+                progress,
+            ),
+            #[cfg(feature = "std")]
+            (
+                Automata::Nfa { nfa, relaxed },
+                _,
+                Caches::Look {
+                    relaxed: relaxed_cache,
+                    nfa: cache,
+                },
+            ) => dfa::search_look_around(
+                &mut self.input,
+                Lazy::new(relaxed, relaxed_cache),
+                live,
+                nfa,
+                cache,
+                kind,
+                stop_when_skipped,
+                progress,
+            ),
+            #[cfg(feature = "std")]
+            (Automata::Lazy { nfa, .. } | Automata::Nfa { nfa, .. }, _, Caches::Nfa(cache)) => {
+                pikevm::find(
+                    nfa,
+                    cache,
+                    kind,
+                    &mut self.input,
+                    stop_when_skipped,
+                    None,
+                    progress,
+                )
+                .map(|(_, mat)| mat)
+            }
+            #[cfg(feature = "alloc")]
+            _ => unreachable!("the caches are created for the automata"),
+        };
+
+        match result {
+            Ok(mat) => Ok(mat),
+            Err(Stop::Step(step)) => Err(step),
+            Err(Stop::Pending(progress)) => {
+                self.progress = Some(progress);
+                Err(Step::Done)
+            }
+            #[cfg(not(feature = "std"))]
+            Err(Stop::GaveUp) => unreachable!("only lazy DFAs give up"),
+            #[cfg(not(feature = "std"))]
+            Err(Stop::Truncated) => {
+                self.truncated = true;
+                Err(Step::Done)
+            }
+            #[cfg(feature = "std")]
+            Err(Stop::GaveUp) => {
+                let nfa = match self.dfa {
+                    Automata::Lazy { nfa, .. } | Automata::Nfa { nfa, .. } => nfa,
+                    _ => unreachable!("only lazy DFAs give up"),
+                };
+
+                // The cache thrashes, so search again from the start with the NFA instead.
+                let restart = search_start.max(self.input.window_start());
+                self.input.unread(restart);
+                self.caches = Caches::Nfa(pikevm::Cache::new(nfa));
+                self.search()
+            }
+            #[cfg(feature = "std")]
+            Err(Stop::TooLong) => {
+                // Only this match is searched for with the NFA, which can drop most of its bytes.
+                let restart = search_start.max(self.input.window_start());
+                self.input.unread(restart);
+                self.search_too_long(None)
+            }
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Matches<'_, Haystack> {
+    /// Searches for the rest of a match which got too long for the DFAs, see [Stop::TooLong].
+    #[cfg(feature = "std")]
+    fn search_too_long(&mut self, progress: Option<Progress>) -> Result<Range<u64>, Step> {
+        let (nfa, cache) = &mut self.too_long;
+        let nfa = nfa.expect("the window is only limited with an NFA to go on with");
+        let cache = cache.get_or_insert_with(|| pikevm::Cache::new(nfa));
+        let result = pikevm::find(
+            nfa,
+            cache,
+            self.kind,
+            &mut self.input,
+            self.stop_when_skipped,
+            None,
+            progress,
+        );
+
+        match result {
+            Ok((_, mat)) => Ok(mat),
+            Err(Stop::Step(step)) => Err(step),
+            Err(Stop::Pending(Progress::Nfa { mat, .. })) => {
+                self.progress = Some(Progress::TooLong { mat });
+                Err(Step::Done)
+            }
+            Err(_) => unreachable!("the NFA doesn't give up"),
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for Matches<'_, Haystack> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_match().map(usize_range)
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for MatchesFrom<'_, Haystack> {
+    type Item = Range<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next_match()
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for RMatches<'_, Haystack> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mat = self.matches.next()?;
+        let flip = |i| {
+            self.len
+                .checked_sub(i)
+                .expect("the haystack is longer than its length")
+        };
+        Some(flip(mat.end)..flip(mat.start))
+    }
+}
+
+/// Converts an offset into the haystack to `usize`.
+///
+/// # Panics
+///
+/// Panics if the offset doesn't fit, which can only happen on 32-bit targets.
+fn to_usize(offset: u64) -> usize {
+    usize::try_from(offset).expect("offset overflows usize, use Regex::matches_from instead")
+}
+
+fn usize_range(range: Range<u64>) -> Range<usize> {
+    to_usize(range.start)..to_usize(range.end)
+}
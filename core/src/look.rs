@@ -0,0 +1,229 @@
+//! Look-around assertions, which match depending on the bytes around a position.
+
+use std::str;
+
+use regex_syntax::{is_word_byte, is_word_character};
+
+use crate::input::Input;
+
+/// An assertion about the bytes around a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Look {
+    /// The start of the haystack.
+    StartText,
+    /// The end of the haystack.
+    EndText,
+    /// The start of a line, i.e. after `\n`.
+    StartLine,
+    /// The end of a line, i.e. before `\n`.
+    EndLine,
+    /// The start of a line, i.e. after `\n` or `\r`, but not between `\r\n`.
+    StartLineCrlf,
+    /// The end of a line, i.e. before `\n` or `\r`, but not between `\r\n`.
+    EndLineCrlf,
+    /// An ASCII word boundary.
+    WordAscii,
+    /// Not an ASCII word boundary.
+    NotWordAscii,
+    /// A Unicode word boundary.
+    WordUnicode,
+    /// Not a Unicode word boundary.
+    NotWordUnicode,
+}
+
+/// A set of assertions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct LookSet(u16);
+
+impl Look {
+    const ALL: [Look; 10] = [
+        Look::StartText,
+        Look::EndText,
+        Look::StartLine,
+        Look::EndLine,
+        Look::StartLineCrlf,
+        Look::EndLineCrlf,
+        Look::WordAscii,
+        Look::NotWordAscii,
+        Look::WordUnicode,
+        Look::NotWordUnicode,
+    ];
+
+    /// Returns the assertion with the given index, the inverse of `look as u8`.
+    pub(crate) fn from_index(index: u8) -> Option<Look> {
+        Look::ALL.get(usize::from(index)).copied()
+    }
+
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+impl LookSet {
+    pub(crate) fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub(crate) fn contains(self, look: Look) -> bool {
+        self.0 & look.bit() != 0
+    }
+
+    pub(crate) fn insert(&mut self, look: Look) {
+        self.0 |= look.bit();
+    }
+
+    /// Returns whether the bytes after `at` which the assertions look at were read,
+    /// so they can be checked while more bytes of a partial input might follow.
+    /// That's the next byte, and for Unicode word boundaries all bytes of the character there.
+    pub(crate) fn can_check<Haystack: Iterator<Item = u8>>(
+        self,
+        input: &mut Input<Haystack>,
+        at: u64,
+        reverse: bool,
+    ) -> bool {
+        if input.byte_at(at).is_none() {
+            return false;
+        }
+        if !self.contains(Look::WordUnicode) && !self.contains(Look::NotWordUnicode) {
+            return true;
+        }
+
+        // Decode the character the way `satisfied` does, noting if a byte it needs is missing.
+        let mut read = true;
+        let byte = |i: usize| {
+            let b = input.byte_at(at + i as u64);
+            read &= b.is_some();
+            b
+        };
+        match reverse {
+            false => char_after(byte),
+            true => char_before(byte),
+        };
+        read
+    }
+
+    /// Returns which of the assertions in `self` hold at `at`.
+    /// If `reverse` is set, the input is the haystack read backwards.
+    pub(crate) fn satisfied<Haystack: Iterator<Item = u8>>(
+        self,
+        input: &mut Input<Haystack>,
+        at: u64,
+        reverse: bool,
+    ) -> LookSet {
+        let mut satisfied = LookSet::default();
+
+        if self.is_empty() {
+            return satisfied;
+        }
+
+        // The bytes around the position, in the order of the original haystack.
+        let prev = outward(input, at, 0, !reverse);
+        let next = outward(input, at, 0, reverse);
+
+        let is_word = |b: Option<u8>| b.is_some_and(is_word_byte);
+        let word_ascii = is_word(prev) != is_word(next);
+
+        let (word_unicode, not_word_unicode) =
+            if self.contains(Look::WordUnicode) || self.contains(Look::NotWordUnicode) {
+                let is_word = |c: Option<char>| c.is_some_and(is_word_character);
+                let before = char_before(|i| outward(input, at, i, !reverse));
+                let after = char_after(|i| outward(input, at, i, reverse));
+                let word = is_word(before) != is_word(after);
+                // Like the regex crate, don't split a character, or bytes which aren't one, with `\B`.
+                let decoded =
+                    (prev.is_none() || before.is_some()) && (next.is_none() || after.is_some());
+                (word, !word && decoded)
+            } else {
+                (false, false)
+            };
+
+        let checks = [
+            (Look::StartText, prev.is_none()),
+            (Look::EndText, next.is_none()),
+            (Look::StartLine, matches!(prev, None | Some(b'\n'))),
+            (Look::EndLine, matches!(next, None | Some(b'\n'))),
+            (
+                Look::StartLineCrlf,
+                match prev {
+                    None | Some(b'\n') => true,
+                    Some(b'\r') => next != Some(b'\n'),
+                    _ => false,
+                },
+            ),
+            (
+                Look::EndLineCrlf,
+                match next {
+                    None | Some(b'\r') => true,
+                    Some(b'\n') => prev != Some(b'\r'),
+                    _ => false,
+                },
+            ),
+            (Look::WordAscii, word_ascii),
+            (Look::NotWordAscii, !word_ascii),
+            (Look::WordUnicode, word_unicode),
+            (Look::NotWordUnicode, not_word_unicode),
+        ];
+
+        for (look, holds) in checks {
+            if holds && self.contains(look) {
+                satisfied.insert(look);
+            }
+        }
+
+        satisfied
+    }
+}
+
+/// Returns the `i`th byte going away from `at`, either backwards or forwards.
+fn outward<Haystack: Iterator<Item = u8>>(
+    input: &mut Input<Haystack>,
+    at: u64,
+    i: usize,
+    backwards: bool,
+) -> Option<u8> {
+    if backwards {
+        input.byte_at(at.checked_sub(i as u64 + 1)?)
+    } else {
+        input.byte_at(at + i as u64)
+    }
+}
+
+/// Decodes the character ending at a position, given its bytes going backwards.
+fn char_before(mut byte: impl FnMut(usize) -> Option<u8>) -> Option<char> {
+    let mut buf = [0; 4];
+
+    for len in 1..=buf.len() {
+        let b = byte(len - 1)?;
+        buf[buf.len() - len] = b;
+
+        // Stop at anything but a continuation byte.
+        if b & 0xC0 != 0x80 {
+            return decode(&buf[buf.len() - len..]);
+        }
+    }
+
+    None
+}
+
+/// Decodes the character starting at a position, given its bytes going forwards.
+fn char_after(mut byte: impl FnMut(usize) -> Option<u8>) -> Option<char> {
+    let lead = byte(0)?;
+    let len = match lead {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return None,
+    };
+
+    let mut buf = [lead, 0, 0, 0];
+    for (i, b) in buf.iter_mut().enumerate().take(len).skip(1) {
+        *b = byte(i)?;
+    }
+
+    decode(&buf[..len])
+}
+
+fn decode(bytes: &[u8]) -> Option<char> {
+    str::from_utf8(bytes).ok()?.chars().next()
+}
@@ -0,0 +1,802 @@
+//! A Thompson NFA compiled from the parsed regex.
+//! The DFAs can't track capture groups, so this is used to resolve them once a match is known.
+//! It also supports look-around, so it is used for searching if the regex needs that.
+
+use std::{mem, sync::Arc};
+
+use regex_syntax::{
+    hir::{
+        Anchor, Class, GroupKind, Hir, HirKind, Literal, RepetitionKind, RepetitionRange,
+        WordBoundary,
+    },
+    utf8::Utf8Sequences,
+};
+
+use crate::{
+    look::{Look, LookSet},
+    serialize::{Reader, Writer},
+    Error,
+};
+
+pub(crate) type StateId = usize;
+
+/// A state of the NFA.
+#[derive(Debug, Clone)]
+pub(crate) enum State {
+    /// Transition to `next` on any byte in `start..=end`.
+    Range { start: u8, end: u8, next: StateId },
+    /// Epsilon transitions to all alternates, preferring earlier ones.
+    Union { alternates: Vec<StateId> },
+    /// Record the current position in `slot`, then continue with `next`.
+    Capture { slot: usize, next: StateId },
+    /// Continue with `next` if the assertion holds at the current position.
+    Look { look: Look, next: StateId },
+    /// The regex with the given index matched.
+    Match { pattern: usize },
+}
+
+/// A compiled NFA.
+#[derive(Debug, Clone)]
+pub(crate) struct Nfa {
+    states: Vec<State>,
+    start: StateId,
+    patterns: usize,
+    /// The name of each capture group, if any.
+    names: Arc<[Option<String>]>,
+    /// The assertions used anywhere in the NFA.
+    looks: LookSet,
+    /// Whether the NFA matches the reversed regex, so it runs over the haystack backwards.
+    reverse: bool,
+}
+
+impl Nfa {
+    /// Compile the NFA for the given expression.
+    /// If `crlf` is set, `\r\n` is treated as a line terminator as well.
+    /// Fails if the states would take up more than `size_limit` bytes.
+    pub(crate) fn new(hir: &Hir, crlf: bool, size_limit: usize) -> Result<Nfa, SizeLimitExceeded> {
+        let mut names = vec![None];
+        collect_names(hir, &mut names);
+
+        let mut compiler = Compiler::new(true, false, crlf, size_limit);
+        let fragment = compiler.capture(0, hir)?;
+        let end = compiler.push(State::Match { pattern: 0 })?;
+        compiler.patch(fragment.end, end);
+        compiler.check()?;
+
+        Ok(Nfa {
+            states: compiler.states,
+            start: fragment.start,
+            patterns: 1,
+            names: names.into(),
+            looks: compiler.looks,
+            reverse: false,
+        })
+    }
+
+    /// Compile a single NFA matching any of the given expressions.
+    /// Earlier expressions are preferred, and only the implicit group 0 is captured.
+    pub(crate) fn new_many(
+        hirs: &[Hir],
+        crlf: bool,
+        size_limit: usize,
+    ) -> Result<Nfa, SizeLimitExceeded> {
+        Nfa::compile_many(hirs, false, crlf, size_limit)
+    }
+
+    /// Compile the NFA matching any of the given expressions backwards, like [Nfa::new_many].
+    pub(crate) fn new_reverse(
+        hirs: &[Hir],
+        crlf: bool,
+        size_limit: usize,
+    ) -> Result<Nfa, SizeLimitExceeded> {
+        Nfa::compile_many(hirs, true, crlf, size_limit)
+    }
+
+    fn compile_many(
+        hirs: &[Hir],
+        reverse: bool,
+        crlf: bool,
+        size_limit: usize,
+    ) -> Result<Nfa, SizeLimitExceeded> {
+        let mut compiler = Compiler::new(false, reverse, crlf, size_limit);
+        let start = compiler.empty()?;
+        for (pattern, hir) in hirs.iter().enumerate() {
+            let fragment = compiler.capture(0, hir)?;
+            let end = compiler.push(State::Match { pattern })?;
+            compiler.patch(start, fragment.start);
+            compiler.patch(fragment.end, end);
+        }
+        compiler.check()?;
+
+        Ok(Nfa {
+            states: compiler.states,
+            start,
+            patterns: hirs.len(),
+            names: vec![None].into(),
+            looks: compiler.looks,
+            reverse,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub(crate) fn start(&self) -> StateId {
+        self.start
+    }
+
+    pub(crate) fn state(&self, id: StateId) -> &State {
+        &self.states[id]
+    }
+
+    /// The number of expressions matched by this NFA.
+    pub(crate) fn patterns(&self) -> usize {
+        self.patterns
+    }
+
+    /// The number of slots needed to record all capture groups.
+    pub(crate) fn slots(&self) -> usize {
+        self.names.len() * 2
+    }
+
+    pub(crate) fn names(&self) -> &Arc<[Option<String>]> {
+        &self.names
+    }
+
+    pub(crate) fn looks(&self) -> LookSet {
+        self.looks
+    }
+
+    pub(crate) fn is_reverse(&self) -> bool {
+        self.reverse
+    }
+
+    /// Returns whether every thread records where its match starts before anything else,
+    /// which the Pike VM relies on. Compiled NFAs always do, loaded ones might not.
+    pub(crate) fn captures_start(&self) -> bool {
+        let is_capture = |id| matches!(self.states[id], State::Capture { slot: 0, .. });
+        match &self.states[self.start] {
+            State::Union { alternates } => alternates.iter().all(|&id| is_capture(id)),
+            _ => is_capture(self.start),
+        }
+    }
+
+    /// The number of bytes the NFA uses on the heap.
+    pub(crate) fn memory_usage(&self) -> usize {
+        let alternates = self
+            .states
+            .iter()
+            .map(|state| match state {
+                State::Union { alternates } => alternates.capacity() * mem::size_of::<StateId>(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        let names = self
+            .names
+            .iter()
+            .map(|name| name.as_ref().map_or(0, String::capacity))
+            .sum::<usize>();
+
+        self.states.capacity() * mem::size_of::<State>()
+            + alternates
+            + mem::size_of_val(&*self.names)
+            + names
+    }
+
+    /// Builds the NFA matching what this NFA matches backwards, without capture groups.
+    /// Look-around assertions are assumed to hold, so this may match more.
+    pub(crate) fn reversed(&self) -> Nfa {
+        // Each state keeps its ID, and becomes a union of the states it was reached from.
+        let mut states = vec![State::Union { alternates: vec![] }; self.states.len()];
+        let mut matches = vec![];
+
+        for (id, state) in self.states.iter().enumerate() {
+            let (from, to) = match *state {
+                State::Range { start, end, next } => {
+                    states.push(State::Range {
+                        start,
+                        end,
+                        next: id,
+                    });
+                    (states.len() - 1, next)
+                }
+                State::Union { ref alternates } => {
+                    for &alt in alternates {
+                        push_alternate(&mut states, alt, id);
+                    }
+                    continue;
+                }
+                State::Capture { next, .. } | State::Look { next, .. } => (id, next),
+                State::Match { .. } => {
+                    matches.push(id);
+                    continue;
+                }
+            };
+            push_alternate(&mut states, to, from);
+        }
+
+        let end = states.len();
+        states.push(State::Match { pattern: 0 });
+        push_alternate(&mut states, self.start, end);
+        states.push(State::Union {
+            alternates: matches,
+        });
+
+        Nfa {
+            start: states.len() - 1,
+            states,
+            patterns: 1,
+            names: vec![None].into(),
+            looks: LookSet::default(),
+            reverse: !self.reverse,
+        }
+    }
+
+    /// Builds the NFA matching the suffixes of what this NFA matches, by starting in any state.
+    /// Look-around assertions are assumed to hold, so this may match more.
+    pub(crate) fn suffixes(&self) -> Nfa {
+        let mut states = self
+            .states
+            .iter()
+            .map(|state| match *state {
+                State::Capture { next, .. } | State::Look { next, .. } => State::Union {
+                    alternates: vec![next],
+                },
+                ref state => state.clone(),
+            })
+            .collect::<Vec<_>>();
+        states.push(State::Union {
+            alternates: (0..self.states.len()).collect(),
+        });
+
+        Nfa {
+            start: states.len() - 1,
+            states,
+            patterns: self.patterns,
+            names: vec![None].into(),
+            looks: LookSet::default(),
+            reverse: self.reverse,
+        }
+    }
+
+    /// Builds the NFA for which look-around assertions always hold, so it may match more.
+    /// Unlike this one, it can be run by a lazy DFA.
+    pub(crate) fn without_looks(&self) -> Nfa {
+        let states = self
+            .states
+            .iter()
+            .map(|state| match *state {
+                State::Look { next, .. } => State::Union {
+                    alternates: vec![next],
+                },
+                ref state => state.clone(),
+            })
+            .collect();
+
+        Nfa {
+            start: self.start,
+            states,
+            patterns: self.patterns,
+            names: self.names.clone(),
+            looks: LookSet::default(),
+            reverse: self.reverse,
+        }
+    }
+}
+
+/// Adds an epsilon transition from the union `from` to `to`.
+fn push_alternate(states: &mut [State], from: StateId, to: StateId) {
+    match &mut states[from] {
+        State::Union { alternates } => alternates.push(to),
+        _ => unreachable!("only unions get alternates"),
+    }
+}
+
+impl Nfa {
+    /// Appends the NFA to the serialized regex.
+    pub(crate) fn write(&self, w: &mut Writer) {
+        w.usize(self.start);
+        w.usize(self.patterns);
+        w.u8(self.reverse.into());
+
+        w.usize(self.names.len());
+        for name in self.names.iter() {
+            match name {
+                Some(name) => {
+                    w.u8(1);
+                    w.bytes(name.as_bytes());
+                }
+                None => w.u8(0),
+            }
+        }
+
+        w.usize(self.states.len());
+        for state in &self.states {
+            match state {
+                State::Range { start, end, next } => {
+                    w.u8(0);
+                    w.u8(*start);
+                    w.u8(*end);
+                    w.usize(*next);
+                }
+                State::Union { alternates } => {
+                    w.u8(1);
+                    w.usize(alternates.len());
+                    for &id in alternates {
+                        w.usize(id);
+                    }
+                }
+                State::Capture { slot, next } => {
+                    w.u8(2);
+                    w.usize(*slot);
+                    w.usize(*next);
+                }
+                State::Look { look, next } => {
+                    w.u8(3);
+                    w.u8(*look as u8);
+                    w.usize(*next);
+                }
+                State::Match { pattern } => {
+                    w.u8(4);
+                    w.usize(*pattern);
+                }
+            }
+        }
+    }
+
+    /// Reads an NFA written by [Nfa::write], checking that all states and slots it refers to exist.
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Nfa, Error> {
+        let start = r.usize()?;
+        let patterns = r.usize()?;
+        let reverse = r.u8()? != 0;
+
+        let len = r.usize()?;
+        let mut names = Vec::with_capacity(len.min(r.remaining()));
+        for _ in 0..len {
+            names.push(match r.u8()? {
+                0 => None,
+                _ => Some(r.str()?.to_owned()),
+            });
+        }
+        let slots = names.len() * 2;
+
+        let len = r.usize()?;
+        let id = |r: &mut Reader<'_>| match r.usize()? {
+            id if id < len => Ok(id),
+            _ => Err(r.error("NFA state out of bounds")),
+        };
+
+        let mut states = Vec::with_capacity(len.min(r.remaining()));
+        let mut looks = LookSet::default();
+        for _ in 0..len {
+            let state = match r.u8()? {
+                0 => State::Range {
+                    start: r.u8()?,
+                    end: r.u8()?,
+                    next: id(r)?,
+                },
+                1 => {
+                    let count = r.usize()?;
+                    let mut alternates = Vec::with_capacity(count.min(r.remaining()));
+                    for _ in 0..count {
+                        alternates.push(id(r)?);
+                    }
+                    State::Union { alternates }
+                }
+                2 => match (r.usize()?, id(r)?) {
+                    (slot, next) if slot < slots => State::Capture { slot, next },
+                    _ => return Err(r.error("capture slot out of bounds")),
+                },
+                3 => {
+                    let look =
+                        Look::from_index(r.u8()?).ok_or_else(|| r.error("unknown assertion"))?;
+                    looks.insert(look);
+                    State::Look { look, next: id(r)? }
+                }
+                4 => match r.usize()? {
+                    pattern if pattern < patterns => State::Match { pattern },
+                    _ => return Err(r.error("pattern out of bounds")),
+                },
+                _ => return Err(r.error("unknown NFA state")),
+            };
+            states.push(state);
+        }
+
+        if start >= len {
+            return Err(r.error("NFA state out of bounds"));
+        }
+
+        Ok(Nfa {
+            states,
+            start,
+            patterns,
+            names: names.into(),
+            looks,
+            reverse,
+        })
+    }
+}
+
+/// Compiling an NFA stopped, because its states would take up too much memory.
+#[derive(Debug)]
+pub(crate) struct SizeLimitExceeded;
+
+/// Returns whether the expression contains anchors or word boundaries.
+/// Those are not supported by the DFAs.
+pub(crate) fn has_look_around(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Anchor(_) | HirKind::WordBoundary(_) => true,
+        HirKind::Group(group) => has_look_around(&group.hir),
+        HirKind::Repetition(rep) => has_look_around(&rep.hir),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs.iter().any(has_look_around),
+        _ => false,
+    }
+}
+
+/// Roughly estimates the number of states of a dense DFA for the expression.
+/// Counted repetitions of anything but literals may multiply the states,
+/// as every combination of positions within them might have to be tracked at once.
+pub(crate) fn dfa_states_estimate(hir: &Hir) -> u64 {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => 1,
+        HirKind::Literal(Literal::Unicode(c)) => c.len_utf8() as u64,
+        HirKind::Literal(Literal::Byte(_)) | HirKind::Class(Class::Bytes(_)) => 1,
+        HirKind::Class(Class::Unicode(class)) => class
+            .iter()
+            .flat_map(|range| Utf8Sequences::new(range.start(), range.end()))
+            .map(|seq| seq.as_slice().len() as u64)
+            .sum(),
+        HirKind::Repetition(rep) => {
+            let states = dfa_states_estimate(&rep.hir);
+            let count = match &rep.kind {
+                RepetitionKind::Range(RepetitionRange::Exactly(n))
+                | RepetitionKind::Range(RepetitionRange::AtLeast(n))
+                | RepetitionKind::Range(RepetitionRange::Bounded(_, n)) => *n,
+                _ => 1,
+            };
+            let factor = if is_literal(&rep.hir) {
+                u64::from(count)
+            } else {
+                1u64.checked_shl(count).unwrap_or(u64::MAX)
+            };
+            states.saturating_mul(factor)
+        }
+        HirKind::Group(group) => dfa_states_estimate(&group.hir),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs
+            .iter()
+            .map(dfa_states_estimate)
+            .fold(0, u64::saturating_add),
+    }
+}
+
+fn is_literal(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Literal(_) => true,
+        HirKind::Group(group) => is_literal(&group.hir),
+        HirKind::Concat(hirs) => hirs.iter().all(is_literal),
+        _ => false,
+    }
+}
+
+fn collect_names(hir: &Hir, names: &mut Vec<Option<String>>) {
+    match hir.kind() {
+        HirKind::Group(group) => {
+            match &group.kind {
+                GroupKind::CaptureIndex(index) => set_name(names, *index, None),
+                GroupKind::CaptureName { name, index } => {
+                    set_name(names, *index, Some(name.clone()))
+                }
+                GroupKind::NonCapturing => {}
+            }
+            collect_names(&group.hir, names);
+        }
+        HirKind::Repetition(rep) => collect_names(&rep.hir, names),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+            for hir in hirs {
+                collect_names(hir, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn set_name(names: &mut Vec<Option<String>>, index: u32, name: Option<String>) {
+    let index = index as usize;
+    if names.len() <= index {
+        names.resize(index + 1, None);
+    }
+    names[index] = name;
+}
+
+/// A part of the NFA with a single entry and a single exit.
+/// The exit is always a [State::Union], so it can be patched to continue anywhere.
+struct Fragment {
+    start: StateId,
+    end: StateId,
+}
+
+struct Compiler {
+    states: Vec<State>,
+    /// Whether to record capture groups other than group 0.
+    captures: bool,
+    /// Whether to compile the reversed expression.
+    reverse: bool,
+    /// Whether line anchors treat `\r\n` as a line terminator.
+    crlf: bool,
+    looks: LookSet,
+    /// The number of bytes the states may take up.
+    size_limit: usize,
+    memory: usize,
+}
+
+impl Compiler {
+    fn new(captures: bool, reverse: bool, crlf: bool, size_limit: usize) -> Compiler {
+        Compiler {
+            states: vec![],
+            captures,
+            reverse,
+            crlf,
+            looks: LookSet::default(),
+            size_limit,
+            memory: 0,
+        }
+    }
+
+    fn push(&mut self, state: State) -> Result<StateId, SizeLimitExceeded> {
+        self.memory += mem::size_of::<State>();
+        self.check()?;
+        self.states.push(state);
+        Ok(self.states.len() - 1)
+    }
+
+    fn empty(&mut self) -> Result<StateId, SizeLimitExceeded> {
+        self.push(State::Union { alternates: vec![] })
+    }
+
+    fn check(&self) -> Result<(), SizeLimitExceeded> {
+        if self.memory > self.size_limit {
+            return Err(SizeLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Add a transition from `from` to `to`.
+    fn patch(&mut self, from: StateId, to: StateId) {
+        match &mut self.states[from] {
+            State::Range { next, .. } | State::Capture { next, .. } | State::Look { next, .. } => {
+                *next = to
+            }
+            State::Union { alternates } => {
+                self.memory += mem::size_of::<StateId>();
+                alternates.push(to)
+            }
+            State::Match { .. } => {}
+        }
+    }
+
+    fn compile(&mut self, hir: &Hir) -> Result<Fragment, SizeLimitExceeded> {
+        let fragment = match hir.kind() {
+            HirKind::Empty => {
+                let id = self.empty()?;
+                Fragment { start: id, end: id }
+            }
+            HirKind::Literal(Literal::Unicode(c)) => {
+                let mut buf = [0; 4];
+                let bytes = c.encode_utf8(&mut buf).as_bytes();
+                self.sequence(bytes.iter().map(|&b| (b, b)))?
+            }
+            HirKind::Literal(Literal::Byte(b)) => self.sequence([(*b, *b)])?,
+            HirKind::Class(Class::Unicode(class)) => {
+                let start = self.empty()?;
+                let end = self.empty()?;
+                for range in class.iter() {
+                    for seq in Utf8Sequences::new(range.start(), range.end()) {
+                        let fragment =
+                            self.sequence(seq.as_slice().iter().map(|r| (r.start, r.end)))?;
+                        self.patch(start, fragment.start);
+                        self.patch(fragment.end, end);
+                    }
+                }
+                Fragment { start, end }
+            }
+            HirKind::Class(Class::Bytes(class)) => {
+                let start = self.empty()?;
+                let end = self.empty()?;
+                for range in class.iter() {
+                    let fragment = self.sequence([(range.start(), range.end())])?;
+                    self.patch(start, fragment.start);
+                    self.patch(fragment.end, end);
+                }
+                Fragment { start, end }
+            }
+            HirKind::Anchor(anchor) => self.look(match (anchor, self.crlf) {
+                (Anchor::StartText, _) => Look::StartText,
+                (Anchor::EndText, _) => Look::EndText,
+                (Anchor::StartLine, false) => Look::StartLine,
+                (Anchor::EndLine, false) => Look::EndLine,
+                (Anchor::StartLine, true) => Look::StartLineCrlf,
+                (Anchor::EndLine, true) => Look::EndLineCrlf,
+            })?,
+            HirKind::WordBoundary(boundary) => self.look(match boundary {
+                WordBoundary::Unicode => Look::WordUnicode,
+                WordBoundary::UnicodeNegate => Look::NotWordUnicode,
+                WordBoundary::Ascii => Look::WordAscii,
+                WordBoundary::AsciiNegate => Look::NotWordAscii,
+            })?,
+            HirKind::Repetition(rep) => match &rep.kind {
+                RepetitionKind::ZeroOrOne => self.optional(&rep.hir, rep.greedy)?,
+                RepetitionKind::ZeroOrMore => self.star(&rep.hir, rep.greedy)?,
+                RepetitionKind::OneOrMore => self.plus(&rep.hir, rep.greedy)?,
+                RepetitionKind::Range(RepetitionRange::Exactly(n)) => self.repeat(&rep.hir, *n)?,
+                RepetitionKind::Range(RepetitionRange::AtLeast(n)) => {
+                    let head = self.repeat(&rep.hir, *n)?;
+                    let tail = self.star(&rep.hir, rep.greedy)?;
+                    self.patch(head.end, tail.start);
+                    Fragment {
+                        start: head.start,
+                        end: tail.end,
+                    }
+                }
+                RepetitionKind::Range(RepetitionRange::Bounded(min, max)) => {
+                    let head = self.repeat(&rep.hir, *min)?;
+                    let end = self.empty()?;
+                    let mut prev = head.end;
+                    for _ in *min..*max {
+                        let fragment = self.optional(&rep.hir, rep.greedy)?;
+                        self.patch(prev, fragment.start);
+                        prev = fragment.end;
+                    }
+                    self.patch(prev, end);
+                    Fragment {
+                        start: head.start,
+                        end,
+                    }
+                }
+            },
+            HirKind::Group(group) => match &group.kind {
+                GroupKind::CaptureIndex(index) | GroupKind::CaptureName { index, .. }
+                    if self.captures =>
+                {
+                    self.capture(*index as usize, &group.hir)?
+                }
+                _ => self.compile(&group.hir)?,
+            },
+            HirKind::Concat(hirs) => {
+                let start = self.empty()?;
+                let mut end = start;
+                let mut hirs = hirs.iter().collect::<Vec<_>>();
+                if self.reverse {
+                    hirs.reverse();
+                }
+                for hir in hirs {
+                    let fragment = self.compile(hir)?;
+                    self.patch(end, fragment.start);
+                    end = fragment.end;
+                }
+                Fragment { start, end }
+            }
+            HirKind::Alternation(hirs) => {
+                let start = self.empty()?;
+                let end = self.empty()?;
+                for hir in hirs {
+                    let fragment = self.compile(hir)?;
+                    self.patch(start, fragment.start);
+                    self.patch(fragment.end, end);
+                }
+                Fragment { start, end }
+            }
+        };
+        Ok(fragment)
+    }
+
+    /// Compile a chain of byte ranges, which is reversed when compiling backwards.
+    fn sequence(
+        &mut self,
+        ranges: impl IntoIterator<Item = (u8, u8)>,
+    ) -> Result<Fragment, SizeLimitExceeded> {
+        let mut ranges = ranges.into_iter().collect::<Vec<_>>();
+        if self.reverse {
+            ranges.reverse();
+        }
+
+        let start = self.empty()?;
+        let mut end = start;
+        for (lo, hi) in ranges {
+            let id = self.push(State::Range {
+                start: lo,
+                end: hi,
+                next: 0,
+            })?;
+            self.patch(end, id);
+            end = id;
+        }
+        let exit = self.empty()?;
+        self.patch(end, exit);
+        Ok(Fragment { start, end: exit })
+    }
+
+    fn look(&mut self, look: Look) -> Result<Fragment, SizeLimitExceeded> {
+        self.looks.insert(look);
+        let start = self.push(State::Look { look, next: 0 })?;
+        let exit = self.empty()?;
+        self.patch(start, exit);
+        Ok(Fragment { start, end: exit })
+    }
+
+    fn capture(&mut self, index: usize, hir: &Hir) -> Result<Fragment, SizeLimitExceeded> {
+        let start = self.push(State::Capture {
+            slot: index * 2,
+            next: 0,
+        })?;
+        let fragment = self.compile(hir)?;
+        let end = self.push(State::Capture {
+            slot: index * 2 + 1,
+            next: 0,
+        })?;
+        let exit = self.empty()?;
+        self.patch(start, fragment.start);
+        self.patch(fragment.end, end);
+        self.patch(end, exit);
+        Ok(Fragment { start, end: exit })
+    }
+
+    fn repeat(&mut self, hir: &Hir, n: u32) -> Result<Fragment, SizeLimitExceeded> {
+        let start = self.empty()?;
+        let mut end = start;
+        for _ in 0..n {
+            let fragment = self.compile(hir)?;
+            self.patch(end, fragment.start);
+            end = fragment.end;
+        }
+        Ok(Fragment { start, end })
+    }
+
+    fn optional(&mut self, hir: &Hir, greedy: bool) -> Result<Fragment, SizeLimitExceeded> {
+        let start = self.empty()?;
+        let end = self.empty()?;
+        let fragment = self.compile(hir)?;
+        if greedy {
+            self.patch(start, fragment.start);
+            self.patch(start, end);
+        } else {
+            self.patch(start, end);
+            self.patch(start, fragment.start);
+        }
+        self.patch(fragment.end, end);
+        Ok(Fragment { start, end })
+    }
+
+    fn star(&mut self, hir: &Hir, greedy: bool) -> Result<Fragment, SizeLimitExceeded> {
+        let start = self.empty()?;
+        let end = self.empty()?;
+        let fragment = self.compile(hir)?;
+        if greedy {
+            self.patch(start, fragment.start);
+            self.patch(start, end);
+        } else {
+            self.patch(start, end);
+            self.patch(start, fragment.start);
+        }
+        self.patch(fragment.end, start);
+        Ok(Fragment { start, end })
+    }
+
+    fn plus(&mut self, hir: &Hir, greedy: bool) -> Result<Fragment, SizeLimitExceeded> {
+        let fragment = self.compile(hir)?;
+        let repeat = self.empty()?;
+        let end = self.empty()?;
+        self.patch(fragment.end, repeat);
+        if greedy {
+            self.patch(repeat, fragment.start);
+            self.patch(repeat, end);
+        } else {
+            self.patch(repeat, end);
+            self.patch(repeat, fragment.start);
+        }
+        Ok(Fragment {
+            start: fragment.start,
+            end,
+        })
+    }
+}
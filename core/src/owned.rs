@@ -0,0 +1,119 @@
+//! Returning the bytes of each match together with its range.
+
+use std::{fmt, ops::Range};
+
+use crate::{usize_range, Matches};
+
+/// A match together with the bytes it matched, see [Matches::with_bytes].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedMatch {
+    /// The range of the match.
+    pub range: Range<usize>,
+    /// The bytes of the match, which are fewer than its length if they were truncated.
+    pub bytes: Vec<u8>,
+}
+
+/// The error for a match longer than the maximum length, see [Matches::with_bytes].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchTooLong {
+    /// The range of the match.
+    pub range: Range<usize>,
+    /// The maximum length of a match.
+    pub max_len: usize,
+}
+
+/// An iterator over the matches with their bytes, see [Matches::with_bytes].
+#[derive(Debug)]
+pub struct WithBytes<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    max_len: usize,
+    truncate: bool,
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Matches<'r, Haystack> {
+    /// Returns an iterator over the matches with the bytes they matched.
+    /// A match longer than `max_len` is returned as an error, after which the search goes on.
+    ///
+    /// Only the first `max_len` bytes of a match are buffered while looking for its end,
+    /// so a long match doesn't take up more memory than a short one.
+    ///
+    /// ```rust
+    /// use hotsauce::{MatchTooLong, Regex};
+    ///
+    /// let regex = Regex::new("[a-z]+").unwrap();
+    /// let mut matches = regex.matches("hello 123 moon".bytes()).with_bytes(4);
+    /// assert_eq!(matches.next(), Some(Err(MatchTooLong { range: 0..5, max_len: 4 })));
+    ///
+    /// let mat = matches.next().unwrap().unwrap();
+    /// assert_eq!(mat.range, 10..14);
+    /// assert_eq!(mat.bytes, b"moon");
+    /// ```
+    pub fn with_bytes(mut self, max_len: usize) -> WithBytes<'r, Haystack> {
+        // Without an NFA to go on with, a long match has to be buffered until it ends.
+        if self.too_long.0.is_some() {
+            self.input.set_max_len(u64::try_from(max_len).ok());
+        }
+        WithBytes {
+            matches: self,
+            max_len,
+            truncate: false,
+        }
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> WithBytes<'r, Haystack> {
+    /// Set whether a match longer than the maximum length keeps its first bytes
+    /// instead of being returned as an error.
+    /// Disabled by default.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let regex = Regex::new("[a-z]+").unwrap();
+    /// let mat = regex.matches("hello".bytes()).with_bytes(4).truncate(true).next();
+    /// assert_eq!(mat.unwrap().unwrap().bytes, b"hell");
+    /// ```
+    pub fn truncate(mut self, yes: bool) -> WithBytes<'r, Haystack> {
+        self.truncate = yes;
+        self
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for WithBytes<'_, Haystack> {
+    type Item = Result<OwnedMatch, MatchTooLong>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mat = self.matches.next_match()?;
+        let range = usize_range(mat.clone());
+        if range.len() > self.max_len && !self.truncate {
+            return Some(Err(MatchTooLong {
+                range,
+                max_len: self.max_len,
+            }));
+        }
+
+        // The first bytes of the match are still in the window of the search.
+        let bytes = self
+            .matches
+            .input
+            .window_from(mat.start)
+            .take_while(|&(i, _)| i < mat.end)
+            .take(self.max_len)
+            .map(|(_, b)| b)
+            .collect();
+
+        Some(Ok(OwnedMatch { range, bytes }))
+    }
+}
+
+impl fmt::Display for MatchTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "match at {}..{} exceeds maximum length of {} bytes",
+            self.range.start, self.range.end, self.max_len
+        )
+    }
+}
+
+impl std::error::Error for MatchTooLong {}
@@ -0,0 +1,440 @@
+//! A Pike VM simulating the [Nfa] while keeping track of capture groups.
+//!
+//! Look-around assertions depend on the byte after a position as well,
+//! so the states reached by a byte are only expanded once the next byte is known.
+
+use std::ops::Range;
+
+use crate::{
+    dfa::Stop,
+    input::Input,
+    look::LookSet,
+    nfa::{Nfa, State, StateId},
+    MatchKind, Progress, Step,
+};
+
+/// The fewest bytes dropped from the middle of the window at once, as the bytes after them are moved.
+const MIN_GAP: u64 = 256;
+
+/// Scratch space for running the Pike VM, reused between searches.
+#[derive(Debug, Clone)]
+pub(crate) struct Cache {
+    clist: Threads,
+    nlist: Threads,
+    stack: Vec<Frame>,
+    slots: Vec<Option<u64>>,
+}
+
+/// The active threads, in order of priority, with the slots recorded by each.
+#[derive(Debug, Clone)]
+struct Threads {
+    set: SparseSet,
+    slots: Vec<Option<u64>>,
+    stride: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Frame {
+    Explore(StateId),
+    Restore { slot: usize, value: Option<u64> },
+}
+
+/// An ordered set of states with constant time insertion and clearing.
+#[derive(Debug, Clone)]
+pub(crate) struct SparseSet {
+    dense: Vec<StateId>,
+    sparse: Vec<usize>,
+}
+
+impl Cache {
+    pub(crate) fn new(nfa: &Nfa) -> Cache {
+        Cache {
+            clist: Threads::new(nfa),
+            nlist: Threads::new(nfa),
+            stack: vec![],
+            slots: vec![None; nfa.slots()],
+        }
+    }
+}
+
+impl Threads {
+    fn new(nfa: &Nfa) -> Threads {
+        Threads {
+            set: SparseSet::new(nfa.len()),
+            slots: vec![None; nfa.len() * nfa.slots()],
+            stride: nfa.slots(),
+        }
+    }
+
+    fn slots(&self, id: StateId) -> &[Option<u64>] {
+        &self.slots[id * self.stride..(id + 1) * self.stride]
+    }
+
+    fn slots_mut(&mut self, id: StateId) -> &mut [Option<u64>] {
+        &mut self.slots[id * self.stride..(id + 1) * self.stride]
+    }
+
+    /// Adds a state reached by a byte, to be expanded at the next position.
+    fn seed(&mut self, id: StateId, slots: &[Option<u64>]) {
+        if self.set.insert(id) {
+            self.slots_mut(id).copy_from_slice(slots);
+        }
+    }
+}
+
+impl SparseSet {
+    pub(crate) fn new(capacity: usize) -> SparseSet {
+        SparseSet {
+            dense: Vec::with_capacity(capacity),
+            sparse: vec![0; capacity],
+        }
+    }
+
+    /// Inserts the state, returning whether it was newly inserted.
+    pub(crate) fn insert(&mut self, id: StateId) -> bool {
+        let i = self.sparse[id];
+        if i < self.dense.len() && self.dense[i] == id {
+            return false;
+        }
+        self.sparse[id] = self.dense.len();
+        self.dense.push(id);
+        true
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+/// Finds the capture groups of the preferred way for the NFA to match exactly `span`.
+/// The bytes of the span have to be in the window of the input.
+pub(crate) fn captures<Haystack: Iterator<Item = u8>>(
+    nfa: &Nfa,
+    cache: &mut Cache,
+    input: &mut Input<Haystack>,
+    span: Range<u64>,
+) -> Option<Vec<Option<u64>>> {
+    let Cache {
+        clist,
+        nlist,
+        stack,
+        slots,
+    } = cache;
+
+    nlist.set.clear();
+
+    for at in span.clone() {
+        let looks = nfa.looks().satisfied(input, at, nfa.is_reverse());
+        expand(nfa, clist, nlist, stack, slots, at, looks, at == span.start);
+
+        let b = input.byte_at(at).expect("the span is in the window");
+        nlist.set.clear();
+
+        for i in 0..clist.set.dense.len() {
+            let id = clist.set.dense[i];
+            if let State::Range { start, end, next } = *nfa.state(id) {
+                if start <= b && b <= end {
+                    nlist.seed(next, clist.slots(id));
+                }
+            }
+        }
+    }
+
+    let looks = nfa.looks().satisfied(input, span.end, nfa.is_reverse());
+    expand(
+        nfa,
+        clist,
+        nlist,
+        stack,
+        slots,
+        span.end,
+        looks,
+        span.is_empty(),
+    );
+
+    clist
+        .set
+        .dense
+        .iter()
+        .find(|&&id| matches!(nfa.state(id), State::Match { .. }))
+        .map(|&id| clist.slots(id).to_vec())
+}
+
+/// Finds the leftmost match of any pattern in the NFA, returning the pattern and the match.
+/// Bytes read past the end of the match are put back into the input.
+/// The window of the input is kept from the start of the earliest thread on,
+/// unless it gets longer than the input keeps, see [Input::set_max_len] and [Input::set_max_window].
+/// If `until` is set, the search stops without a match once none can start up to there.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn find<Haystack: Iterator<Item = u8>>(
+    nfa: &Nfa,
+    cache: &mut Cache,
+    kind: MatchKind,
+    input: &mut Input<Haystack>,
+    stop_when_skipped: bool,
+    until: Option<u64>,
+    progress: Option<Progress>,
+) -> Result<(usize, Range<u64>), Stop> {
+    let Cache {
+        clist,
+        nlist,
+        stack,
+        slots,
+    } = cache;
+
+    // Waiting for more bytes left the threads in `nlist`.
+    let (mut mat, until) = match progress {
+        Some(Progress::Nfa { mat, until }) => (mat, until),
+        _ => {
+            nlist.set.clear();
+            (None, until)
+        }
+    };
+
+    loop {
+        let at = input.position();
+
+        if mat.is_none() && nlist.set.dense.is_empty() {
+            match until {
+                // The DFA can look for where the next match might be.
+                Some(until) if at > until => return Err(Step::Skipped.into()),
+                // The bytes up to `until` were kept in the window anyway.
+                Some(_) => {}
+                // No match can start before this point anymore.
+                None if stop_when_skipped && input.window().next().is_some() => {
+                    return Err(Step::Skipped.into());
+                }
+                None => input.clear_window(),
+            }
+        }
+
+        // Look-around needs the bytes after `at`, so the threads are only expanded once they're there.
+        if input.is_partial() && !nfa.looks().can_check(input, at, nfa.is_reverse()) {
+            return Err(Stop::Pending(Progress::Nfa { mat, until }));
+        }
+
+        let looks = nfa.looks().satisfied(input, at, nfa.is_reverse());
+        expand(nfa, clist, nlist, stack, slots, at, looks, mat.is_none());
+
+        let b = input.read_byte();
+        nlist.set.clear();
+
+        for i in 0..clist.set.dense.len() {
+            let id = clist.set.dense[i];
+            let start = || clist.slots(id)[0].expect("every pattern captures group 0");
+
+            match *nfa.state(id) {
+                State::Match { pattern } => {
+                    let start = start();
+                    let better = match (&mat, kind) {
+                        (Some((_, m)), MatchKind::LeftmostLongest) => {
+                            start < m.start || (start == m.start && at > m.end)
+                        }
+                        _ => true,
+                    };
+
+                    if better {
+                        mat = Some((pattern, start..at));
+                    }
+
+                    if kind == MatchKind::LeftmostFirst {
+                        // Threads with lower priority can't win anymore.
+                        break;
+                    }
+                }
+                State::Range {
+                    start: lo,
+                    end: hi,
+                    next,
+                } => {
+                    if !matches!(b, Some(b) if lo <= b && b <= hi) {
+                        continue;
+                    }
+
+                    if let Some((_, m)) = &mat {
+                        if start() > m.start {
+                            continue;
+                        }
+                    }
+
+                    nlist.seed(next, clist.slots(id));
+                }
+                _ => {}
+            }
+        }
+
+        if b.is_none() || (mat.is_some() && nlist.set.dense.is_empty()) {
+            break;
+        }
+
+        if let Some(max_len) = input.max_len() {
+            drop_unneeded(input, nlist, &mat, max_len);
+        } else if input.is_over_max_window() {
+            // Only where the match is has to be known, not its bytes.
+            let to = mat.as_ref().map_or(input.position(), |(_, m)| m.end);
+            input.drop_before(to);
+        }
+    }
+
+    let (pattern, m) = mat.ok_or(Step::Done)?;
+    input.unread(m.end);
+    Ok((pattern, m))
+}
+
+/// Drops the bytes of the window no match needs anymore, when only the first `max_len` bytes
+/// of a match are kept. The bytes after the end of a match are kept to be searched again.
+fn drop_unneeded<Haystack: Iterator<Item = u8>>(
+    input: &mut Input<Haystack>,
+    threads: &Threads,
+    mat: &Option<(usize, Range<u64>)>,
+    max_len: u64,
+) {
+    let starts = threads
+        .set
+        .dense
+        .iter()
+        .map(|&id| threads.slots(id)[0].expect("every pattern captures group 0"));
+    let from = match starts.chain(mat.as_ref().map(|(_, m)| m.start)).max() {
+        Some(start) => start.saturating_add(max_len),
+        None => return,
+    };
+
+    let to = mat.as_ref().map_or(input.position(), |(_, m)| m.end);
+    if to > from && to - from >= max_len.max(MIN_GAP) {
+        input.drop_between(from, to);
+    }
+}
+
+/// Finds which patterns of the NFA match anywhere in the input.
+/// If `any` is set, this stops at the first match.
+pub(crate) fn which<Haystack: Iterator<Item = u8>>(
+    nfa: &Nfa,
+    cache: &mut Cache,
+    input: &mut Input<Haystack>,
+    any: bool,
+) -> Vec<bool> {
+    let Cache {
+        clist,
+        nlist,
+        stack,
+        slots,
+    } = cache;
+
+    let mut matched = vec![false; nfa.patterns()];
+    let mut left = nfa.patterns();
+
+    nlist.set.clear();
+
+    loop {
+        let at = input.position();
+        let looks = nfa.looks().satisfied(input, at, nfa.is_reverse());
+        expand(nfa, clist, nlist, stack, slots, at, looks, true);
+
+        for &id in &clist.set.dense {
+            if let State::Match { pattern } = *nfa.state(id) {
+                if !matched[pattern] {
+                    matched[pattern] = true;
+                    left -= 1;
+                }
+            }
+        }
+
+        if left == 0 || (any && left < nfa.patterns()) {
+            break;
+        }
+
+        let b = match input.next_byte() {
+            Some(b) => b,
+            None => break,
+        };
+
+        nlist.set.clear();
+
+        for i in 0..clist.set.dense.len() {
+            let id = clist.set.dense[i];
+            if let State::Range { start, end, next } = *nfa.state(id) {
+                if start <= b && b <= end {
+                    nlist.seed(next, clist.slots(id));
+                }
+            }
+        }
+    }
+
+    matched
+}
+
+/// Computes the threads at `at` from the states reached by the last byte, in `seeds`.
+/// If `start` is set, a thread starting at `at` is added with the lowest priority.
+#[allow(clippy::too_many_arguments)]
+fn expand(
+    nfa: &Nfa,
+    threads: &mut Threads,
+    seeds: &Threads,
+    stack: &mut Vec<Frame>,
+    slots: &mut [Option<u64>],
+    at: u64,
+    looks: LookSet,
+    start: bool,
+) {
+    threads.set.clear();
+
+    for &id in &seeds.set.dense {
+        slots.copy_from_slice(seeds.slots(id));
+        add(nfa, threads, stack, slots, id, at, looks);
+    }
+
+    if start {
+        slots.fill(None);
+        add(nfa, threads, stack, slots, nfa.start(), at, looks);
+    }
+}
+
+/// Adds the epsilon closure of `id` to the threads, with `slots` as recorded so far.
+/// Look-around assertions are only followed if they are in `looks`.
+fn add(
+    nfa: &Nfa,
+    threads: &mut Threads,
+    stack: &mut Vec<Frame>,
+    slots: &mut [Option<u64>],
+    id: StateId,
+    at: u64,
+    looks: LookSet,
+) {
+    stack.push(Frame::Explore(id));
+
+    while let Some(frame) = stack.pop() {
+        let id = match frame {
+            Frame::Explore(id) => id,
+            Frame::Restore { slot, value } => {
+                slots[slot] = value;
+                continue;
+            }
+        };
+
+        if !threads.set.insert(id) {
+            continue;
+        }
+
+        match nfa.state(id) {
+            State::Range { .. } | State::Match { .. } => {
+                threads.slots_mut(id).copy_from_slice(slots)
+            }
+            State::Union { alternates } => {
+                stack.extend(alternates.iter().rev().map(|&alt| Frame::Explore(alt)));
+            }
+            State::Capture { slot, next } => {
+                stack.push(Frame::Restore {
+                    slot: *slot,
+                    value: slots[*slot],
+                });
+                slots[*slot] = Some(at);
+                stack.push(Frame::Explore(*next));
+            }
+            State::Look { look, next } => {
+                if looks.contains(*look) {
+                    stack.push(Frame::Explore(*next));
+                }
+            }
+        }
+    }
+}
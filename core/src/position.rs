@@ -0,0 +1,163 @@
+//! Reporting matches as characters, lines and columns instead of bytes.
+
+use std::ops::Range;
+
+use crate::{usize_range, Matches, Step};
+
+/// A match with its position in characters and lines, see [Matches::positions].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// The range of the match in bytes.
+    pub bytes: Range<usize>,
+    /// The range of the match in UTF-8 code points.
+    pub chars: Range<usize>,
+    /// The position of the start of the match.
+    pub start: LineColumn,
+    /// The position of the end of the match.
+    pub end: LineColumn,
+}
+
+/// A position in the haystack as a line and column, both starting at 1.
+/// The column counts UTF-8 code points, with tabs advancing to the next tab stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineColumn {
+    /// The line, starting at 1.
+    pub line: usize,
+    /// The column, starting at 1.
+    pub column: usize,
+}
+
+/// An iterator over the matches with their positions in characters and lines,
+/// see [Matches::positions].
+#[derive(Debug)]
+pub struct Positions<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    counter: Counter,
+}
+
+/// Keeps track of the position after the bytes seen so far.
+#[derive(Debug)]
+struct Counter {
+    chars: usize,
+    position: LineColumn,
+    crlf: bool,
+    tab_width: usize,
+    /// Whether the last byte was `\r`.
+    cr: bool,
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Matches<'r, Haystack> {
+    /// Returns an iterator over the matches with their positions in characters and lines.
+    /// Every byte of the haystack is counted as it is searched.
+    ///
+    /// By default, lines end with `\n` and a tab is one column wide.
+    ///
+    /// ```rust
+    /// use hotsauce::{LineColumn, Regex};
+    ///
+    /// let regex = Regex::new("wörld").unwrap();
+    /// let mat = regex.matches("hëllo\n\twörld".bytes()).positions().tab_width(4).next().unwrap();
+    /// assert_eq!(mat.bytes, 8..14);
+    /// assert_eq!(mat.chars, 7..12);
+    /// assert_eq!(mat.start, LineColumn { line: 2, column: 5 });
+    /// assert_eq!(mat.end, LineColumn { line: 2, column: 10 });
+    /// ```
+    pub fn positions(mut self) -> Positions<'r, Haystack> {
+        // Lines and columns are counted from every byte before a match,
+        // so the search has to hand them over before they're dropped.
+        self.stop_when_skipped = true;
+        self.keep_match_bytes();
+
+        Positions {
+            matches: self,
+            counter: Counter {
+                chars: 0,
+                position: LineColumn { line: 1, column: 1 },
+                crlf: false,
+                tab_width: 1,
+                cr: false,
+            },
+        }
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Positions<'r, Haystack> {
+    /// Set whether lines end with `\r\n` instead of `\n`, so a lone `\n` doesn't end a line.
+    /// Disabled by default.
+    pub fn crlf(mut self, yes: bool) -> Positions<'r, Haystack> {
+        self.counter.crlf = yes;
+        self
+    }
+
+    /// Set the distance between tab stops, in columns.
+    /// Defaults to 1, so a tab is a single column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is 0.
+    pub fn tab_width(mut self, width: usize) -> Positions<'r, Haystack> {
+        assert!(width > 0, "tab width must be positive");
+        self.counter.tab_width = width;
+        self
+    }
+}
+
+impl Counter {
+    fn advance(&mut self, b: u8) {
+        let starts_char = b & 0xc0 != 0x80;
+        if starts_char {
+            self.chars += 1;
+        }
+
+        let position = &mut self.position;
+        match b {
+            b'\n' if !self.crlf || self.cr => {
+                position.line += 1;
+                position.column = 1;
+            }
+            b'\t' => {
+                position.column =
+                    (position.column - 1) / self.tab_width * self.tab_width + self.tab_width + 1;
+            }
+            _ if starts_char => position.column += 1,
+            _ => {}
+        }
+
+        self.cr = b == b'\r';
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for Positions<'_, Haystack> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.matches.step() {
+                Step::Match(mat) => {
+                    let input = &self.matches.input;
+                    for (_, b) in input.window().take_while(|&(i, _)| i < mat.start) {
+                        self.counter.advance(b);
+                    }
+                    let (start_char, start) = (self.counter.chars, self.counter.position);
+
+                    for (_, b) in input.window_from(mat.start) {
+                        self.counter.advance(b);
+                    }
+
+                    return Some(Match {
+                        bytes: usize_range(mat),
+                        chars: start_char..self.counter.chars,
+                        start,
+                        end: self.counter.position,
+                    });
+                }
+                Step::Skipped => {
+                    for (_, b) in self.matches.input.window() {
+                        self.counter.advance(b);
+                    }
+                }
+                Step::Done => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{collections::VecDeque, str};
+
+use crate::{nfa::Nfa, pikevm, Captures, Matches, Step};
+
+/// Produces the replacement for a match, see [Regex::replace_all](crate::Regex::replace_all).
+///
+/// Byte strings and strings are expanded as templates:
+/// `$n` and `${n}` are replaced by the capture group with index `n`,
+/// `$name` and `${name}` by the group with that name, and `$$` by a literal `$`.
+/// Groups which didn't participate in the match expand to nothing.
+/// Wrap a replacement in [NoExpand] to use it literally instead.
+///
+/// Closures are called with the bytes of each match and return the replacement.
+pub trait Replacer {
+    /// Appends the replacement for a match to `dst`.
+    /// `bytes` are the bytes of the whole match, `caps` the positions of its capture groups.
+    fn replace_append(&mut self, bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>);
+
+    /// Returns whether the replacement depends on capture groups other than group 0.
+    /// If it doesn't, resolving them is skipped and only group 0 is available.
+    fn needs_captures(&self) -> bool {
+        true
+    }
+}
+
+/// A replacement which is used literally, without expanding `$` templates.
+#[derive(Debug, Clone)]
+pub struct NoExpand<T>(pub T);
+
+/// An iterator over the bytes of the haystack, with all matches replaced.
+#[derive(Debug)]
+pub struct ReplaceAll<'r, Haystack: Iterator<Item = u8>, R> {
+    matches: Matches<'r, Haystack>,
+    nfa: &'r Nfa,
+    /// Only present if the replacer needs capture groups.
+    cache: Option<pikevm::Cache>,
+    replacer: R,
+    /// Bytes ready to be yielded.
+    out: VecDeque<u8>,
+    bytes: Vec<u8>,
+    replacement: Vec<u8>,
+    /// Whether no more matches can be found, so the haystack is passed through.
+    done: bool,
+}
+
+impl<T: AsRef<[u8]>> Replacer for NoExpand<T> {
+    fn replace_append(&mut self, _bytes: &[u8], _caps: &Captures, dst: &mut Vec<u8>) {
+        dst.extend_from_slice(self.0.as_ref());
+    }
+
+    fn needs_captures(&self) -> bool {
+        false
+    }
+}
+
+impl Replacer for &[u8] {
+    fn replace_append(&mut self, bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>) {
+        expand(self, bytes, caps, dst);
+    }
+
+    fn needs_captures(&self) -> bool {
+        self.contains(&b'$')
+    }
+}
+
+impl<const N: usize> Replacer for &[u8; N] {
+    fn replace_append(&mut self, bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>) {
+        expand(*self, bytes, caps, dst);
+    }
+
+    fn needs_captures(&self) -> bool {
+        self.contains(&b'$')
+    }
+}
+
+impl Replacer for Vec<u8> {
+    fn replace_append(&mut self, bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>) {
+        expand(self, bytes, caps, dst);
+    }
+
+    fn needs_captures(&self) -> bool {
+        self.contains(&b'$')
+    }
+}
+
+impl Replacer for &str {
+    fn replace_append(&mut self, bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>) {
+        expand(self.as_bytes(), bytes, caps, dst);
+    }
+
+    fn needs_captures(&self) -> bool {
+        self.contains('$')
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>) {
+        expand(self.as_bytes(), bytes, caps, dst);
+    }
+
+    fn needs_captures(&self) -> bool {
+        self.contains('$')
+    }
+}
+
+impl<F: FnMut(&[u8]) -> Vec<u8>> Replacer for F {
+    fn replace_append(&mut self, bytes: &[u8], _caps: &Captures, dst: &mut Vec<u8>) {
+        dst.extend_from_slice(&self(bytes));
+    }
+
+    fn needs_captures(&self) -> bool {
+        false
+    }
+}
+
+/// Expands the `$` references in the template.
+fn expand(mut template: &[u8], bytes: &[u8], caps: &Captures, dst: &mut Vec<u8>) {
+    let offset = caps.get(0).expect("group 0 always participates").start;
+
+    while let Some(i) = template.iter().position(|&b| b == b'$') {
+        dst.extend_from_slice(&template[..i]);
+        template = &template[i + 1..];
+
+        if template.first() == Some(&b'$') {
+            dst.push(b'$');
+            template = &template[1..];
+            continue;
+        }
+
+        let (name, rest) = match parse_reference(template) {
+            Some(reference) => reference,
+            None => {
+                dst.push(b'$');
+                continue;
+            }
+        };
+        template = rest;
+
+        let group = match name.parse() {
+            Ok(i) => caps.get(i),
+            Err(_) => caps.name(name),
+        };
+
+        if let Some(group) = group {
+            dst.extend_from_slice(&bytes[group.start - offset..group.end - offset]);
+        }
+    }
+
+    dst.extend_from_slice(template);
+}
+
+/// Parses the group reference following a `$`, returning it and the rest of the template.
+fn parse_reference(template: &[u8]) -> Option<(&str, &[u8])> {
+    let (name, rest) = if template.first() == Some(&b'{') {
+        let end = template.iter().position(|&b| b == b'}')?;
+        (&template[1..end], &template[end + 1..])
+    } else {
+        let len = template
+            .iter()
+            .take_while(|&&b| b == b'_' || b.is_ascii_alphanumeric())
+            .count();
+        template.split_at(len)
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    Some((str::from_utf8(name).ok()?, rest))
+}
+
+impl<'r, Haystack: Iterator<Item = u8>, R: Replacer> ReplaceAll<'r, Haystack, R> {
+    pub(crate) fn new(
+        mut matches: Matches<'r, Haystack>,
+        nfa: &'r Nfa,
+        replacer: R,
+    ) -> ReplaceAll<'r, Haystack, R> {
+        matches.stop_when_skipped = true;
+        matches.keep_match_bytes();
+
+        ReplaceAll {
+            matches,
+            nfa,
+            cache: replacer.needs_captures().then(|| pikevm::Cache::new(nfa)),
+            replacer,
+            out: VecDeque::new(),
+            bytes: vec![],
+            replacement: vec![],
+            done: false,
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>, R: Replacer> Iterator for ReplaceAll<'_, Haystack, R> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(b) = self.out.pop_front() {
+                return Some(b);
+            }
+
+            if self.done {
+                return self.matches.input.next_byte();
+            }
+
+            match self.matches.step() {
+                Step::Match(mat) => {
+                    let input = &self.matches.input;
+                    self.out.extend(
+                        input
+                            .window()
+                            .take_while(|&(i, _)| i < mat.start)
+                            .map(|(_, b)| b),
+                    );
+
+                    self.bytes.clear();
+                    self.bytes
+                        .extend(input.window_from(mat.start).map(|(_, b)| b));
+
+                    let caps = match &mut self.cache {
+                        Some(cache) => self.matches.captures(self.nfa, cache, mat),
+                        None => Captures::from_match(mat, self.nfa.names()),
+                    };
+
+                    self.replacement.clear();
+                    self.replacer
+                        .replace_append(&self.bytes, &caps, &mut self.replacement);
+                    self.out.extend(&self.replacement);
+                }
+                Step::Skipped => {
+                    self.out.extend(self.matches.input.window().map(|(_, b)| b));
+                }
+                Step::Done => {
+                    self.out.extend(self.matches.input.window().map(|(_, b)| b));
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Searching a haystack which is pushed in chunks instead of pulled from an iterator.
+
+use std::{collections::VecDeque, ops::Range};
+
+use crate::Matches;
+
+/// Searches a haystack fed to it in chunks, see [Regex::searcher](crate::Regex::searcher).
+///
+/// A match is only reported once the bytes fed so far determine it,
+/// so matches spanning chunks are found as if the haystack was searched at once.
+/// The search stops where the fed bytes run out, and goes on from there when more arrive.
+///
+/// ```rust
+/// use hotsauce::Regex;
+///
+/// let regex = Regex::new("[0-9]+").unwrap();
+/// let mut searcher = regex.searcher();
+///
+/// assert_eq!(searcher.feed(b"ab 12").next(), None);
+/// assert_eq!(searcher.feed(b"3 c 4").next(), Some(3..6));
+/// assert_eq!(searcher.finish().next(), Some(9..10));
+/// ```
+#[derive(Debug)]
+pub struct Searcher<'r> {
+    matches: Matches<'r, Fed>,
+}
+
+/// An iterator over the matches completed by the bytes fed to a [Searcher].
+#[derive(Debug)]
+pub struct FedMatches<'s, 'r> {
+    searcher: &'s mut Searcher<'r>,
+}
+
+/// The bytes fed to a [Searcher] which weren't searched yet.
+#[derive(Debug, Default)]
+pub(crate) struct Fed {
+    bytes: VecDeque<u8>,
+    /// Whether the end of the haystack was reached.
+    finished: bool,
+}
+
+impl<'r> Searcher<'r> {
+    pub(crate) fn new(mut matches: Matches<'r, Fed>) -> Searcher<'r> {
+        matches.input.set_partial(true);
+        Searcher { matches }
+    }
+
+    /// Searches the next chunk of the haystack, returning the matches it completes.
+    /// Matches left in the iterator are returned by later calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the searcher was already finished.
+    pub fn feed(&mut self, chunk: &[u8]) -> FedMatches<'_, 'r> {
+        let fed = self.matches.input.haystack_mut();
+        assert!(!fed.finished, "fed a finished searcher");
+        fed.bytes.extend(chunk);
+        FedMatches { searcher: self }
+    }
+
+    /// Ends the haystack, returning the matches which were still in progress.
+    pub fn finish(&mut self) -> FedMatches<'_, 'r> {
+        self.matches.input.haystack_mut().finished = true;
+        self.matches.input.set_partial(false);
+        FedMatches { searcher: self }
+    }
+
+    fn next_match(&mut self) -> Option<Range<u64>> {
+        self.matches.next_match()
+    }
+}
+
+impl Iterator for FedMatches<'_, '_> {
+    type Item = Range<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.searcher.next_match()
+    }
+}
+
+impl Iterator for Fed {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.bytes.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.bytes.len(), None)
+    }
+}
@@ -0,0 +1,839 @@
+//! Serializing a compiled [Regex] to bytes, so it can be loaded without compiling it again.
+//!
+//! The bytes start with a header: a label, an endianness check, the format version
+//! and the size of state IDs. The automata in both directions follow, then the NFA
+//! resolving capture groups. Dense DFAs are stored in the format of `regex-automata`,
+//! aligned to 8 bytes, so they can be searched in place. Compact DFAs are stored the same way,
+//! but always copied when loading.
+//! NFAs are prefixed with their length, so they can be skipped without the `std` feature.
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::slice;
+use core::{
+    cell::UnsafeCell,
+    hint,
+    mem::{self, MaybeUninit},
+    sync::atomic::{AtomicU8, Ordering},
+};
+#[cfg(feature = "std")]
+use std::str;
+
+use regex_automata::DenseDFA;
+#[cfg(feature = "std")]
+use regex_automata::{SparseDFA, StateID};
+
+#[cfg(feature = "std")]
+use crate::{
+    dfa::{with_compact_dfa, CompactDfa},
+    nfa::Nfa,
+};
+use crate::{Automata, Error, MatchKind, Regex};
+
+const LABEL: &[u8; 16] = b"hotsauce-regex\0\0";
+const VERSION: u16 = 2;
+
+/// The label at the start of each dense DFA, see `regex_automata::DenseDFA::from_bytes`.
+const DFA_LABEL: &[u8; 24] = b"rust-regex-automata-dfa\0";
+const DFA_VERSION: u16 = 1;
+const DFA_PREMULTIPLIED: u16 = 0b01;
+const DFA_ANCHORED: u16 = 0b10;
+/// The label at the start of each sparse DFA, see `regex_automata::SparseDFA::from_bytes`.
+#[cfg(feature = "std")]
+const SPARSE_DFA_LABEL: &[u8; 31] = b"rust-regex-automata-sparse-dfa\0";
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endian {
+    Little,
+    Big,
+}
+
+/// Writes integers with the given endianness.
+#[cfg(feature = "std")]
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+    endian: Endian,
+}
+
+/// The fields of a DFA header which are needed to check the DFA.
+struct DfaHeader {
+    options: u16,
+    start_state: usize,
+    state_count: usize,
+    max_match: usize,
+    alphabet_len: usize,
+}
+
+/// A dense DFA copied to a buffer aligned for its state IDs, see [Regex::from_bytes].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub(crate) struct AlignedDfa {
+    buf: Vec<u64>,
+    len: usize,
+}
+
+/// Reads native endian integers, failing if the bytes run out.
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    /// The offset of `buf` in the serialized regex.
+    base: usize,
+}
+
+#[cfg(feature = "std")]
+impl Regex {
+    /// Serializes the regex to bytes in little endian byte order.
+    /// See [Regex::from_bytes] for loading it.
+    pub fn to_bytes_little_endian(&self) -> Vec<u8> {
+        self.to_bytes(Endian::Little)
+    }
+
+    /// Serializes the regex to bytes in big endian byte order.
+    /// See [Regex::from_bytes] for loading it.
+    pub fn to_bytes_big_endian(&self) -> Vec<u8> {
+        self.to_bytes(Endian::Big)
+    }
+
+    /// Serializes the regex to bytes in the byte order of the current target.
+    /// See [Regex::from_bytes] for loading it.
+    ///
+    /// Dense DFAs are stored as they are, so they don't have to be built again when loading.
+    /// State IDs are `usize`, so the bytes can only be loaded on targets with the same
+    /// pointer width and byte order. When serializing in a build script,
+    /// pick the byte order of the target instead.
+    pub fn to_bytes_native_endian(&self) -> Vec<u8> {
+        self.to_bytes(if cfg!(target_endian = "big") {
+            Endian::Big
+        } else {
+            Endian::Little
+        })
+    }
+
+    fn to_bytes(&self, endian: Endian) -> Vec<u8> {
+        let mut w = Writer {
+            buf: vec![],
+            endian,
+        };
+
+        w.buf.extend_from_slice(LABEL);
+        w.u16(0xFEFF);
+        w.u16(VERSION);
+        w.u16(mem::size_of::<usize>() as u16);
+        w.u8(match self.kind {
+            MatchKind::LeftmostFirst => 0,
+            MatchKind::LeftmostLongest => 1,
+        });
+
+        write_automata(&mut w, &self.fw);
+        write_automata(&mut w, &self.bw);
+        w.nfa(&self.nfa);
+        w.buf
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Regex {
+    /// Loads a regex serialized by [Regex::to_bytes_native_endian].
+    ///
+    /// The bytes are fully validated, so loading fails with [Error::Deserialize]
+    /// if they were serialized with a different byte order, pointer width or version,
+    /// or are corrupted. The DFAs are copied, so the bytes need not be aligned.
+    /// Whether the DFAs and the NFA resolving capture groups come from the same regex isn't checked.
+    /// If they don't, groups other than group 0 may not be resolved.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let bytes = Regex::new("foo[0-9]+").unwrap().to_bytes_native_endian();
+    /// let regex = Regex::from_bytes(&bytes).unwrap();
+    /// let mat = regex.matches("a foo12 b".bytes()).next();
+    /// assert_eq!(Some(2..7), mat);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Regex, Error> {
+        read_regex(bytes, |r| {
+            Ok(Automata::AlignedDfa {
+                unanchored: AlignedDfa::load(r, false)?,
+                anchored: AlignedDfa::load(r, true)?,
+            })
+        })
+    }
+}
+
+impl Regex {
+    /// Loads a regex serialized by [Regex::to_bytes_native_endian] without copying its DFAs.
+    ///
+    /// Only the headers are validated, not the transitions of the DFAs.
+    /// Compact DFAs, see [RegexBuilder::sparse](crate::RegexBuilder::sparse), are copied and fully validated.
+    /// Loading fails with [Error::Deserialize] if the bytes aren't aligned to 8 bytes.
+    /// Without the `std` feature, only regexes searched with dense DFAs can be loaded,
+    /// and capture groups are not available.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must have been returned by [Regex::to_bytes_native_endian],
+    /// on a target with the same pointer width and byte order.
+    /// Searching with DFAs built from other bytes may access memory out of bounds.
+    ///
+    /// ```rust
+    /// use hotsauce::Regex;
+    ///
+    /// let bytes = Regex::new("foo[0-9]+").unwrap().to_bytes_native_endian();
+    ///
+    /// // Copy the bytes to a buffer aligned to 8 bytes.
+    /// let words: &'static mut [u64] = vec![0; bytes.len() / 8 + 1].leak();
+    /// let aligned: &'static mut [u8] =
+    ///     unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr().cast(), bytes.len()) };
+    /// aligned.copy_from_slice(&bytes);
+    ///
+    /// let regex = unsafe { Regex::from_bytes_unchecked(aligned) }.unwrap();
+    /// let mat = regex.matches("a foo12 b".bytes()).next();
+    /// assert_eq!(Some(2..7), mat);
+    /// ```
+    pub unsafe fn from_bytes_unchecked(bytes: &'static [u8]) -> Result<Regex, Error> {
+        if bytes.as_ptr() as usize & 7 != 0 {
+            return Err(Error::Deserialize {
+                message: "bytes are not aligned to 8 bytes",
+                offset: 0,
+            });
+        }
+
+        // SAFETY: the caller guarantees the transitions are valid,
+        // `dfa` checked the header and the bytes are aligned.
+        read_regex(bytes, |r| {
+            Ok(Automata::StaticDfa {
+                unanchored: unsafe {
+                    DenseDFA::from_bytes(r.dfa(false, mem::size_of::<usize>(), false)?)
+                },
+                anchored: unsafe {
+                    DenseDFA::from_bytes(r.dfa(true, mem::size_of::<usize>(), false)?)
+                },
+            })
+        })
+    }
+}
+
+/// A regex loaded from static bytes the first time it's used, backing the `regex!` macro.
+/// It only needs `core`, so the macro works in `no_std` crates.
+#[doc(hidden)]
+pub struct StaticRegex {
+    bytes: &'static [u8],
+    state: AtomicU8,
+    regex: UnsafeCell<MaybeUninit<Regex>>,
+}
+
+// SAFETY: the regex is written once, before `state` says it's loaded, and only read after that.
+unsafe impl Sync for StaticRegex where Regex: Sync {}
+
+const UNLOADED: u8 = 0;
+const LOADING: u8 = 1;
+const LOADED: u8 = 2;
+
+impl StaticRegex {
+    /// # Safety
+    ///
+    /// The bytes have to be valid for [Regex::from_bytes_unchecked].
+    pub const unsafe fn new(bytes: &'static [u8]) -> StaticRegex {
+        StaticRegex {
+            bytes,
+            state: AtomicU8::new(UNLOADED),
+            regex: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Returns the regex, loading it unless that happened already.
+    /// Other threads wait while it's loaded, which doesn't take long as nothing is copied.
+    pub fn get(&self) -> &Regex {
+        loop {
+            match self.state.compare_exchange(
+                UNLOADED,
+                LOADING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    // SAFETY: the caller of `new` guarantees the bytes are valid.
+                    let regex = unsafe { Regex::from_bytes_unchecked(self.bytes) }
+                        .expect("the regex was compiled by the macro");
+                    // SAFETY: only the thread which set `LOADING` gets here.
+                    unsafe { (*self.regex.get()).write(regex) };
+                    self.state.store(LOADED, Ordering::Release);
+                }
+                // SAFETY: the regex was written before `LOADED` was stored.
+                Err(LOADED) => return unsafe { (*self.regex.get()).assume_init_ref() },
+                Err(_) => hint::spin_loop(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn write_automata(w: &mut Writer, automata: &Automata) {
+    match automata {
+        Automata::Dfa {
+            unanchored,
+            anchored,
+        } => {
+            w.u8(0);
+            w.dfa(&unanchored.as_ref());
+            w.dfa(&anchored.as_ref());
+        }
+        Automata::StaticDfa {
+            unanchored,
+            anchored,
+        } => {
+            w.u8(0);
+            w.dfa(unanchored);
+            w.dfa(anchored);
+        }
+        Automata::AlignedDfa {
+            unanchored,
+            anchored,
+        } => {
+            w.u8(0);
+            w.dfa(&unanchored.dfa());
+            w.dfa(&anchored.dfa());
+        }
+        Automata::Compact {
+            unanchored,
+            anchored,
+        } => {
+            w.u8(3);
+            w.u8(compact_repr(unanchored));
+            w.compact_dfa(unanchored);
+            w.compact_dfa(anchored);
+        }
+        Automata::Lazy { nfa, cache_size } => {
+            w.u8(1);
+            w.usize(*cache_size);
+            w.nfa(nfa);
+        }
+        Automata::Nfa { nfa, .. } => {
+            w.u8(2);
+            w.nfa(nfa);
+        }
+    }
+}
+
+/// Identifies the representation of a compact DFA.
+#[cfg(feature = "std")]
+fn compact_repr(dfa: &CompactDfa) -> u8 {
+    match dfa {
+        CompactDfa::Dense8(_) => 0,
+        CompactDfa::Dense16(_) => 1,
+        CompactDfa::Dense32(_) => 2,
+        CompactDfa::Sparse8(_) => 3,
+        CompactDfa::Sparse16(_) => 4,
+        CompactDfa::Sparse32(_) => 5,
+        CompactDfa::Sparse(_) => 6,
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AlignedDfa {
+    /// Reads a dense DFA written by [Writer::dfa], checking all transitions, and copies it.
+    fn load(r: &mut Reader<'_>, anchored: bool) -> Result<AlignedDfa, Error> {
+        let bytes = r.dfa(anchored, mem::size_of::<usize>(), true)?;
+        Ok(AlignedDfa::copy(bytes))
+    }
+
+    fn copy(bytes: &[u8]) -> AlignedDfa {
+        let mut buf = vec![0u64; bytes.len().div_ceil(8)];
+        // SAFETY: the buffer is at least as long as the bytes, and any bytes are valid `u64`s.
+        let aligned =
+            unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, bytes.len()) };
+        aligned.copy_from_slice(bytes);
+        AlignedDfa {
+            buf,
+            len: bytes.len(),
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: the buffer holds `len` bytes of the DFA.
+        unsafe { slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.len) }
+    }
+
+    /// Returns the DFA, which is cheap as only its header is read.
+    pub(crate) fn dfa(&self) -> DenseDFA<&[usize], usize> {
+        // SAFETY: the DFA was checked when loading, and the buffer is aligned.
+        unsafe { DenseDFA::from_bytes(self.bytes()) }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.buf.capacity() * mem::size_of::<u64>()
+    }
+}
+
+/// Reads a dense DFA written by [Writer::dfa], checking all transitions,
+/// and copies it to a buffer aligned for its state IDs.
+#[cfg(feature = "std")]
+fn load_dense<S: StateID>(
+    r: &mut Reader<'_>,
+    anchored: bool,
+) -> Result<DenseDFA<Vec<S>, S>, Error> {
+    let bytes = r.dfa(anchored, mem::size_of::<S>(), true)?;
+    let aligned = AlignedDfa::copy(bytes);
+    // SAFETY: `dfa` checked the header, the length and all transitions,
+    // and the buffer is aligned.
+    Ok(unsafe { DenseDFA::from_bytes(aligned.bytes()) }.to_owned())
+}
+
+/// Reads a sparse DFA written by [Writer::dfa], checking all transitions, and copies it.
+#[cfg(feature = "std")]
+fn load_sparse<S: StateID>(
+    r: &mut Reader<'_>,
+    anchored: bool,
+) -> Result<SparseDFA<Vec<u8>, S>, Error> {
+    let bytes = r.sparse_dfa(anchored, mem::size_of::<S>())?;
+    // SAFETY: `sparse_dfa` checked the header and all transitions.
+    Ok(unsafe { SparseDFA::from_bytes(bytes) }.to_owned())
+}
+
+/// Reads compact DFAs written by [write_automata].
+#[cfg(feature = "std")]
+fn read_compact(r: &mut Reader<'_>) -> Result<Automata, Error> {
+    let repr = r.u8()?;
+    let mut load = |anchored| {
+        Ok(match repr {
+            0 => CompactDfa::Dense8(load_dense(r, anchored)?),
+            1 => CompactDfa::Dense16(load_dense(r, anchored)?),
+            2 => CompactDfa::Dense32(load_dense(r, anchored)?),
+            3 => CompactDfa::Sparse8(load_sparse(r, anchored)?),
+            4 => CompactDfa::Sparse16(load_sparse(r, anchored)?),
+            5 => CompactDfa::Sparse32(load_sparse(r, anchored)?),
+            6 => CompactDfa::Sparse(load_sparse(r, anchored)?),
+            _ => return Err(r.error("unknown DFA representation")),
+        })
+    };
+    Ok(Automata::Compact {
+        unanchored: load(false)?,
+        anchored: load(true)?,
+    })
+}
+
+/// Reads the regex, loading the dense DFAs in each direction with `load_dfas`.
+fn read_regex<'a>(
+    bytes: &'a [u8],
+    mut load_dfas: impl FnMut(&mut Reader<'a>) -> Result<Automata, Error>,
+) -> Result<Regex, Error> {
+    let mut r = Reader {
+        buf: bytes,
+        pos: 0,
+        base: 0,
+    };
+    let kind = read_header(&mut r)?;
+
+    let mut read_automata = |r: &mut Reader<'a>, reverse| {
+        Ok(match r.u8()? {
+            0 => load_dfas(r)?,
+            #[cfg(feature = "std")]
+            1 => {
+                let cache_size = r.usize()?;
+                let nfa = read_search_nfa(r, reverse)?;
+                if !nfa.looks().is_empty() {
+                    return Err(r.error("lazy DFA with look-around"));
+                }
+                Automata::Lazy { nfa, cache_size }
+            }
+            #[cfg(feature = "std")]
+            2 => Automata::look_around(read_search_nfa(r, reverse)?),
+            #[cfg(feature = "std")]
+            3 => read_compact(r)?,
+            #[cfg(not(feature = "std"))]
+            1..=3 => {
+                let _ = reverse;
+                return Err(r.error("searching without dense DFAs needs the `std` feature"));
+            }
+            _ => return Err(r.error("unknown automata")),
+        })
+    };
+    let fw = read_automata(&mut r, false)?;
+    let bw = read_automata(&mut r, true)?;
+
+    let same_kind = match (&fw, &bw) {
+        #[cfg(feature = "std")]
+        (Automata::Compact { unanchored: fw, .. }, Automata::Compact { unanchored: bw, .. }) => {
+            compact_repr(fw) == compact_repr(bw)
+        }
+        _ => mem::discriminant(&fw) == mem::discriminant(&bw),
+    };
+    if !same_kind {
+        return Err(r.error("automata of both directions differ"));
+    }
+
+    #[cfg(feature = "std")]
+    let nfa = {
+        let nfa = r.nfa()?;
+        if nfa.patterns() != 1 || nfa.is_reverse() || !nfa.captures_start() {
+            return Err(r.error("unexpected NFA"));
+        }
+        nfa
+    };
+    #[cfg(not(feature = "std"))]
+    r.skip_nfa()?;
+
+    if r.remaining() != 0 {
+        return Err(r.error("trailing bytes"));
+    }
+
+    Ok(Regex {
+        fw,
+        bw,
+        #[cfg(feature = "std")]
+        fw_live: nfa.reversed().suffixes(),
+        #[cfg(feature = "std")]
+        bw_live: nfa.suffixes(),
+        #[cfg(feature = "std")]
+        live_cache_size: crate::DEFAULT_LAZY_CACHE_SIZE,
+        #[cfg(feature = "std")]
+        nfa,
+        kind,
+    })
+}
+
+fn read_header(r: &mut Reader<'_>) -> Result<MatchKind, Error> {
+    if r.take(LABEL.len())? != LABEL {
+        return Err(r.error("not a serialized regex"));
+    }
+
+    match r.u16()? {
+        0xFEFF => {}
+        0xFFFE => return Err(r.error("serialized with a different byte order")),
+        _ => return Err(r.error("invalid endianness check")),
+    }
+
+    if r.u16()? != VERSION {
+        return Err(r.error("unsupported version"));
+    }
+
+    if usize::from(r.u16()?) != mem::size_of::<usize>() {
+        return Err(r.error("serialized with state IDs of another size"));
+    }
+
+    match r.u8()? {
+        0 => Ok(MatchKind::LeftmostFirst),
+        1 => Ok(MatchKind::LeftmostLongest),
+        _ => Err(r.error("unknown match kind")),
+    }
+}
+
+/// Reads an NFA used for searching, which matches a single expression.
+#[cfg(feature = "std")]
+fn read_search_nfa(r: &mut Reader<'_>, reverse: bool) -> Result<Nfa, Error> {
+    let nfa = r.nfa()?;
+    if nfa.patterns() != 1 || nfa.is_reverse() != reverse || !nfa.captures_start() {
+        return Err(r.error("unexpected NFA"));
+    }
+    Ok(nfa)
+}
+
+#[cfg(feature = "std")]
+impl Writer {
+    pub(crate) fn u8(&mut self, n: u8) {
+        self.buf.push(n);
+    }
+
+    pub(crate) fn u16(&mut self, n: u16) {
+        self.buf.extend_from_slice(&match self.endian {
+            Endian::Little => n.to_le_bytes(),
+            Endian::Big => n.to_be_bytes(),
+        });
+    }
+
+    pub(crate) fn u64(&mut self, n: u64) {
+        self.buf.extend_from_slice(&match self.endian {
+            Endian::Little => n.to_le_bytes(),
+            Endian::Big => n.to_be_bytes(),
+        });
+    }
+
+    pub(crate) fn usize(&mut self, n: usize) {
+        self.u64(n as u64);
+    }
+
+    /// Writes the length of the bytes, followed by the bytes.
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.usize(bytes.len());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Writes an NFA, prefixed with its length.
+    fn nfa(&mut self, nfa: &Nfa) {
+        let mut w = Writer {
+            buf: vec![],
+            endian: self.endian,
+        };
+        nfa.write(&mut w);
+        self.bytes(&w.buf);
+    }
+
+    /// Writes a dense DFA, aligned to 8 bytes.
+    fn dfa(&mut self, dfa: &DenseDFA<&[usize], usize>) {
+        let bytes = match self.endian {
+            Endian::Little => dfa.to_bytes_little_endian(),
+            Endian::Big => dfa.to_bytes_big_endian(),
+        }
+        .expect("state IDs of `usize` are supported");
+        self.aligned(&bytes);
+    }
+
+    /// Writes a compact DFA like [Writer::dfa].
+    fn compact_dfa(&mut self, dfa: &CompactDfa) {
+        let bytes = with_compact_dfa!(dfa, dfa => match self.endian {
+            Endian::Little => dfa.to_bytes_little_endian(),
+            Endian::Big => dfa.to_bytes_big_endian(),
+        })
+        .expect("state IDs of 1, 2, 4 or 8 bytes are supported");
+        self.aligned(&bytes);
+    }
+
+    /// Writes the length of the bytes, followed by the bytes aligned to 8 bytes.
+    fn aligned(&mut self, bytes: &[u8]) {
+        self.usize(bytes.len());
+        let padding = (8 - self.buf.len() % 8) % 8;
+        self.buf.resize(self.buf.len() + padding, 0);
+        self.buf.extend_from_slice(bytes);
+    }
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn error(&self, message: &'static str) -> Error {
+        Error::Deserialize {
+            message,
+            offset: self.base + self.pos,
+        }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.remaining() < len {
+            return Err(self.error("unexpected end of bytes"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_ne_bytes([bytes[0], bytes[1]]))
+    }
+
+    #[cfg(feature = "std")]
+    fn u32(&mut self) -> Result<u32, Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_ne_bytes(bytes))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_ne_bytes(bytes))
+    }
+
+    pub(crate) fn usize(&mut self) -> Result<usize, Error> {
+        let n = self.u64()?;
+        usize::try_from(n).map_err(|_| self.error("integer out of range"))
+    }
+
+    /// Reads a string written by [Writer::bytes].
+    #[cfg(feature = "std")]
+    pub(crate) fn str(&mut self) -> Result<&'a str, Error> {
+        let len = self.usize()?;
+        let bytes = self.take(len)?;
+        str::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    /// Reads an NFA written by [Writer::nfa].
+    #[cfg(feature = "std")]
+    fn nfa(&mut self) -> Result<Nfa, Error> {
+        let len = self.usize()?;
+        let end = self.pos + len.min(self.remaining());
+        let nfa = Nfa::read(self)?;
+        if self.pos != end {
+            return Err(self.error("NFA has the wrong length"));
+        }
+        Ok(nfa)
+    }
+
+    /// Skips an NFA written by `Writer::nfa`.
+    #[cfg(not(feature = "std"))]
+    fn skip_nfa(&mut self) -> Result<(), Error> {
+        let len = self.usize()?;
+        self.take(len)?;
+        Ok(())
+    }
+
+    /// Reads a state ID of the given size.
+    fn state_id(&mut self, size: usize) -> Result<usize, Error> {
+        match size {
+            #[cfg(feature = "std")]
+            1 => Ok(usize::from(self.u8()?)),
+            #[cfg(feature = "std")]
+            2 => Ok(usize::from(self.u16()?)),
+            #[cfg(feature = "std")]
+            4 => usize::try_from(self.u32()?).map_err(|_| self.error("integer out of range")),
+            _ => self.usize(),
+        }
+    }
+
+    /// Reads a DFA written by [Writer::aligned], returning its bytes and a reader for them.
+    fn dfa_bytes(&mut self) -> Result<(&'a [u8], Reader<'a>), Error> {
+        let len = self.usize()?;
+        self.take((8 - self.pos % 8) % 8)?;
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        let r = Reader {
+            buf: bytes,
+            pos: 0,
+            base: self.base + start,
+        };
+        Ok((bytes, r))
+    }
+
+    /// Reads the header shared by dense and sparse DFAs, up to their transitions.
+    fn dfa_header(
+        &mut self,
+        label: &[u8],
+        anchored: bool,
+        state_size: usize,
+    ) -> Result<DfaHeader, Error> {
+        if self.take(label.len())? != label {
+            return Err(self.error("invalid DFA label"));
+        }
+        if self.u16()? != 0xFEFF {
+            return Err(self.error("invalid endianness check"));
+        }
+        if self.u16()? != DFA_VERSION {
+            return Err(self.error("unsupported DFA version"));
+        }
+        if usize::from(self.u16()?) != state_size {
+            return Err(self.error("unexpected DFA state ID size"));
+        }
+
+        let options = self.u16()?;
+        if (options & DFA_ANCHORED != 0) != anchored {
+            return Err(self.error("unexpected anchoring"));
+        }
+        let start_state = self.usize()?;
+        let state_count = self.usize()?;
+        let max_match = self.usize()?;
+        let classes = self.take(256)?;
+
+        let alphabet_len = usize::from(classes[255]) + 1;
+        if classes
+            .iter()
+            .any(|&class| usize::from(class) >= alphabet_len)
+        {
+            return Err(self.error("invalid byte classes"));
+        }
+
+        Ok(DfaHeader {
+            options,
+            start_state,
+            state_count,
+            max_match,
+            alphabet_len,
+        })
+    }
+
+    /// Reads a dense DFA written by [Writer::dfa] and checks its header, so loading it can't panic.
+    /// Its state IDs must be `state_size` bytes large.
+    /// If `check_transitions` is set, all transitions are checked to stay in bounds as well.
+    fn dfa(
+        &mut self,
+        anchored: bool,
+        state_size: usize,
+        check_transitions: bool,
+    ) -> Result<&'a [u8], Error> {
+        let (bytes, mut r) = self.dfa_bytes()?;
+        let DfaHeader {
+            options,
+            start_state,
+            state_count,
+            max_match,
+            alphabet_len,
+        } = r.dfa_header(DFA_LABEL, anchored, state_size)?;
+
+        let trans_len = state_count
+            .checked_mul(alphabet_len)
+            .ok_or_else(|| r.error("too many states"))?;
+        if Some(r.remaining()) != trans_len.checked_mul(state_size) {
+            return Err(r.error("transition table has the wrong length"));
+        }
+
+        let premultiplied = options & DFA_PREMULTIPLIED != 0;
+        // `is_multiple_of` would need a newer Rust than the rest of the crate.
+        #[allow(clippy::manual_is_multiple_of)]
+        let is_state = |id: usize| {
+            if premultiplied {
+                id < trans_len && id % alphabet_len == 0
+            } else {
+                id < state_count
+            }
+        };
+
+        if !is_state(start_state) || !is_state(max_match) {
+            return Err(r.error("state out of bounds"));
+        }
+
+        if check_transitions {
+            for _ in 0..trans_len {
+                if !is_state(r.state_id(state_size)?) {
+                    return Err(r.error("transition out of bounds"));
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Reads a sparse DFA written by [Writer::compact_dfa] and checks all of it,
+    /// so searching it stays in bounds.
+    #[cfg(feature = "std")]
+    fn sparse_dfa(&mut self, anchored: bool, state_size: usize) -> Result<&'a [u8], Error> {
+        let (bytes, mut r) = self.dfa_bytes()?;
+        let header = r.dfa_header(SPARSE_DFA_LABEL, anchored, state_size)?;
+
+        // The ID of a state is its offset in the transition table.
+        let trans_start = r.pos;
+        let mut states = vec![];
+        let mut transitions = vec![];
+        while r.remaining() != 0 {
+            states.push(r.pos - trans_start);
+            let len = usize::from(r.u16()?);
+            r.take(2 * len)?;
+            for _ in 0..len {
+                transitions.push((r.pos, r.state_id(state_size)?));
+            }
+        }
+
+        if states.len() != header.state_count {
+            return Err(r.error("transition table has the wrong length"));
+        }
+
+        let is_state = |id: usize| states.binary_search(&id).is_ok();
+        if !is_state(header.start_state) || !is_state(header.max_match) {
+            return Err(r.error("state out of bounds"));
+        }
+        for (pos, id) in transitions {
+            if !is_state(id) {
+                r.pos = pos;
+                return Err(r.error("transition out of bounds"));
+            }
+        }
+
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::Range;
+
+use crate::{
+    dfa::{self, GaveUp, Live, Stop},
+    input::Input,
+    lazy::{self, Lazy},
+    nfa::Nfa,
+    pikevm, usize_range, Error, MatchKind, RegexBuilder,
+};
+
+/// A set of regexes, all searched for in a single pass over the haystack.
+///
+/// ```rust
+/// use hotsauce::RegexSet;
+///
+/// let set = RegexSet::new(["foo", "bar"]).unwrap();
+/// let matches = set.matches("a bar, a foo".bytes()).collect::<Vec<_>>();
+/// assert_eq!(vec![(1, 2..5), (0, 9..12)], matches);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexSet {
+    nfa: Nfa,
+    /// The NFA matching the regexes backwards, to find where the matches of the lazy DFA start.
+    /// Without it, because a regex needs look-around, the set is searched with the Pike VM.
+    rev: Option<Nfa>,
+    /// Tells which bytes no match can start in anymore, see [dfa::Live].
+    live: Nfa,
+    kind: MatchKind,
+    /// The number of bytes the cache of each lazy DFA may use.
+    cache_size: usize,
+}
+
+/// An iterator over the (non-overlapping) matches of any regex in a [RegexSet].
+/// Yields the index of the matching regex together with the match.
+#[derive(Debug)]
+pub struct SetMatches<'r, Haystack: Iterator<Item = u8>> {
+    input: Input<Haystack>,
+    set: &'r RegexSet,
+    caches: Caches,
+    /// The cache of the lazy DFA for [RegexSet::live], created once it is needed.
+    live_cache: Option<lazy::Cache>,
+    needs_advance: bool,
+    /// The end of the last match, an empty match there is skipped like in [crate::Matches].
+    last_end: Option<u64>,
+}
+
+/// The state of the automata used by a single search.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Caches {
+    Lazy {
+        unanchored: lazy::Cache,
+        anchored: lazy::Cache,
+        rev: lazy::Cache,
+    },
+    Nfa(pikevm::Cache),
+}
+
+impl RegexSet {
+    /// Build a new set from the given regexes with default settings (see [RegexBuilder]).
+    pub fn new<I, S>(res: I) -> Result<RegexSet, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        RegexBuilder::new().build_set(res)
+    }
+
+    pub(crate) fn from_nfa(
+        nfa: Nfa,
+        rev: Option<Nfa>,
+        kind: MatchKind,
+        cache_size: usize,
+    ) -> RegexSet {
+        RegexSet {
+            live: nfa.reversed().suffixes(),
+            nfa,
+            rev,
+            kind,
+            cache_size,
+        }
+    }
+
+    /// Returns the NFAs the set is made of.
+    pub(crate) fn nfas(&self) -> impl Iterator<Item = &Nfa> {
+        [&self.nfa, &self.live].into_iter().chain(&self.rev)
+    }
+
+    /// Returns the number of regexes in the set.
+    pub fn len(&self) -> usize {
+        self.nfa.patterns()
+    }
+
+    /// Returns whether the set contains no regexes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the matches of any regex in the set.
+    /// When several regexes match at the same position, the earlier one in the set is preferred.
+    pub fn matches<Haystack: Iterator<Item = u8>>(
+        &self,
+        haystack: Haystack,
+    ) -> SetMatches<'_, Haystack> {
+        let caches = match &self.rev {
+            Some(rev) => Caches::Lazy {
+                unanchored: lazy::Cache::new(&self.nfa, false, self.cache_size),
+                anchored: lazy::Cache::new(&self.nfa, true, self.cache_size),
+                rev: lazy::Cache::new(rev, true, self.cache_size),
+            },
+            None => Caches::Nfa(pikevm::Cache::new(&self.nfa)),
+        };
+
+        SetMatches {
+            input: Input::new(haystack, 0),
+            set: self,
+            caches,
+            live_cache: None,
+            needs_advance: false,
+            last_end: None,
+        }
+    }
+
+    /// Returns whether any regex in the set matches anywhere in the haystack.
+    /// This stops consuming the haystack at the first match.
+    pub fn matches_any<Haystack: Iterator<Item = u8>>(&self, haystack: Haystack) -> bool {
+        let mut input = Input::new(haystack, 0);
+
+        if self.rev.is_some() {
+            let mut cache = lazy::Cache::new(&self.nfa, false, self.cache_size);
+            let mut live_cache = None;
+            let live = Live::new(&self.live, &mut live_cache, self.cache_size);
+            match dfa::is_match(&mut input, &mut Lazy::new(&self.nfa, &mut cache), live) {
+                Ok(matched) => return matched,
+                // The cache thrashes, so search again with the NFA from where a match could start.
+                Err(GaveUp) => input.unread(input.window_start()),
+            }
+        }
+
+        let mut cache = pikevm::Cache::new(&self.nfa);
+        pikevm::which(&self.nfa, &mut cache, &mut input, true).contains(&true)
+    }
+
+    /// Returns the indices of all regexes in the set matching anywhere in the haystack, in order.
+    /// Matches may overlap, so this differs from collecting the indices of [RegexSet::matches].
+    ///
+    /// ```rust
+    /// use hotsauce::RegexSet;
+    ///
+    /// let set = RegexSet::new(["foo", "o+", "bar"]).unwrap();
+    /// assert_eq!(vec![0, 1], set.which_match("foo".bytes()));
+    /// ```
+    pub fn which_match<Haystack: Iterator<Item = u8>>(&self, haystack: Haystack) -> Vec<usize> {
+        let mut cache = pikevm::Cache::new(&self.nfa);
+        pikevm::which(&self.nfa, &mut cache, &mut Input::new(haystack, 0), false)
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, matched)| matched)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> SetMatches<'_, Haystack> {
+    /// Searches for the next match with the lazy DFAs, or the Pike VM once they gave up.
+    fn search(&mut self) -> Option<(usize, Range<u64>)> {
+        let set = self.set;
+        let (unanchored, anchored, rev) = match &mut self.caches {
+            Caches::Lazy {
+                unanchored,
+                anchored,
+                rev,
+            } => (unanchored, anchored, rev),
+            Caches::Nfa(cache) => {
+                return pikevm::find(
+                    &set.nfa,
+                    cache,
+                    set.kind,
+                    &mut self.input,
+                    false,
+                    None,
+                    None,
+                )
+                .ok();
+            }
+        };
+        let rev_nfa = set
+            .rev
+            .as_ref()
+            .expect("lazy DFAs are used with the reverse NFA");
+
+        self.input.clear_window();
+        let search_start = self.input.position();
+        let result = find(
+            &mut self.input,
+            Lazy::new(&set.nfa, unanchored),
+            Lazy::new(&set.nfa, anchored),
+            Lazy::new(rev_nfa, rev),
+            Live::new(&set.live, &mut self.live_cache, set.cache_size),
+            set.kind,
+        );
+
+        match result {
+            Ok(mat) => Some(mat),
+            Err(Stop::Step(_)) => None,
+            Err(Stop::GaveUp) => {
+                // The cache thrashes, so search again from the start with the NFA instead.
+                let restart = search_start.max(self.input.window_start());
+                self.input.unread(restart);
+                self.caches = Caches::Nfa(pikevm::Cache::new(&set.nfa));
+                self.search()
+            }
+            Err(Stop::Pending(_) | Stop::TooLong) => {
+                unreachable!("the input is neither partial nor limited")
+            }
+        }
+    }
+}
+
+/// Finds the next match and the regex it belongs to with lazy DFAs for the regexes of a set,
+/// like [dfa::search] does for a single regex.
+fn find<Haystack: Iterator<Item = u8>>(
+    input: &mut Input<Haystack>,
+    mut unanchored: Lazy<'_>,
+    mut anchored: Lazy<'_>,
+    mut rev: Lazy<'_>,
+    live: Live<'_>,
+    kind: MatchKind,
+) -> Result<(usize, Range<u64>), Stop> {
+    let (search_start, mut end) = dfa::find_end(input, &mut unanchored, Some(live), false, None)?;
+    if kind == MatchKind::LeftmostLongest {
+        let (start, _) = dfa::find_start(input, &mut rev, search_start, end)?;
+        end = dfa::extend_end(input, &mut anchored, search_start, start, end, None)?;
+    }
+
+    // No match starts earlier, so the earliest regex matching from the start to the end wins.
+    let (start, pattern) = dfa::find_start(input, &mut rev, search_start, end)?;
+    Ok((pattern, start..end))
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for SetMatches<'_, Haystack> {
+    type Item = (usize, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.needs_advance {
+                self.input.next_byte()?;
+                self.needs_advance = false;
+            }
+
+            let (pattern, mat) = self.search()?;
+
+            if mat.is_empty() {
+                self.needs_advance = true;
+                if self.last_end == Some(mat.start) {
+                    continue;
+                }
+            }
+            self.last_end = Some(mat.end);
+
+            return Some((pattern, usize_range(mat)));
+        }
+    }
+}
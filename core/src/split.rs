@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+use crate::{to_usize, Matches};
+
+/// A part of the haystack, see [Regex::segments](crate::Regex::segments).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// A match of the regex.
+    Match(Range<usize>),
+    /// The non-empty part between two matches, or before the first or after the last one.
+    Gap(Range<usize>),
+}
+
+/// An iterator over the parts of the haystack between matches.
+#[derive(Debug)]
+pub struct Split<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    /// The end of the last match.
+    last: usize,
+    done: bool,
+}
+
+/// An iterator over at most a given number of parts of the haystack between matches.
+/// The last part is the rest of the haystack.
+#[derive(Debug)]
+pub struct SplitN<'r, Haystack: Iterator<Item = u8>> {
+    split: Split<'r, Haystack>,
+    limit: usize,
+}
+
+/// An iterator over matches and the gaps between them, covering the whole haystack.
+#[derive(Debug)]
+pub struct Segments<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    /// The end of the last match.
+    last: usize,
+    /// A match found after a gap, to be yielded next.
+    pending: Option<Range<usize>>,
+    done: bool,
+}
+
+impl<Haystack: Iterator<Item = u8>> Matches<'_, Haystack> {
+    /// Consumes the rest of the haystack, returning its length.
+    fn skip_to_end(&mut self) -> usize {
+        while self.input.next_byte().is_some() {}
+        to_usize(self.input.position())
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Split<'r, Haystack> {
+    pub(crate) fn new(matches: Matches<'r, Haystack>) -> Split<'r, Haystack> {
+        Split {
+            matches,
+            last: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the rest of the haystack as the final part.
+    fn finish(&mut self) -> Option<Range<usize>> {
+        if self.done {
+            return None;
+        }
+
+        self.done = true;
+        Some(self.last..self.matches.skip_to_end())
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for Split<'_, Haystack> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.matches.next() {
+            Some(mat) => {
+                let part = self.last..mat.start;
+                self.last = mat.end;
+                Some(part)
+            }
+            None => self.finish(),
+        }
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> SplitN<'r, Haystack> {
+    pub(crate) fn new(split: Split<'r, Haystack>, limit: usize) -> SplitN<'r, Haystack> {
+        SplitN { split, limit }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for SplitN<'_, Haystack> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.limit {
+            0 => None,
+            1 => {
+                self.limit = 0;
+                self.split.finish()
+            }
+            _ => {
+                self.limit -= 1;
+                self.split.next()
+            }
+        }
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Segments<'r, Haystack> {
+    pub(crate) fn new(matches: Matches<'r, Haystack>) -> Segments<'r, Haystack> {
+        Segments {
+            matches,
+            last: 0,
+            pending: None,
+            done: false,
+        }
+    }
+}
+
+impl<Haystack: Iterator<Item = u8>> Iterator for Segments<'_, Haystack> {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(mat) = self.pending.take() {
+            return Some(Segment::Match(mat));
+        }
+
+        if self.done {
+            return None;
+        }
+
+        let gap = match self.matches.next() {
+            Some(mat) => {
+                let gap = self.last..mat.start;
+                self.last = mat.end;
+                self.pending = Some(mat);
+                gap
+            }
+            None => {
+                self.done = true;
+                self.last..self.matches.skip_to_end()
+            }
+        };
+
+        if gap.is_empty() {
+            return self.pending.take().map(Segment::Match);
+        }
+
+        Some(Segment::Gap(gap))
+    }
+}
@@ -0,0 +1,143 @@
+//! Searching asynchronous streams and readers, built on [Searcher].
+
+use std::{
+    io,
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::Stream;
+use futures_io::AsyncBufRead;
+
+use crate::Searcher;
+
+/// How many bytes of a stream are fed to the searcher at once.
+const CHUNK_SIZE: usize = 256;
+
+/// A stream of the matches in a stream of bytes, see [Regex::matches_stream](crate::Regex::matches_stream).
+#[derive(Debug)]
+pub struct MatchesStream<'r, S> {
+    searcher: Searcher<'r>,
+    stream: S,
+    ended: bool,
+}
+
+/// A stream of the matches in an asynchronous reader,
+/// see [Regex::matches_async_reader](crate::Regex::matches_async_reader).
+///
+/// Reading fails at most once, after which the stream ends.
+#[derive(Debug)]
+pub struct AsyncReaderMatches<'r, R> {
+    searcher: Searcher<'r>,
+    reader: R,
+    ended: bool,
+    failed: bool,
+}
+
+impl<'r, S> MatchesStream<'r, S> {
+    pub(crate) fn new(searcher: Searcher<'r>, stream: S) -> MatchesStream<'r, S> {
+        MatchesStream {
+            searcher,
+            stream,
+            ended: false,
+        }
+    }
+}
+
+impl<'r, R> AsyncReaderMatches<'r, R> {
+    pub(crate) fn new(searcher: Searcher<'r>, reader: R) -> AsyncReaderMatches<'r, R> {
+        AsyncReaderMatches {
+            searcher,
+            reader,
+            ended: false,
+            failed: false,
+        }
+    }
+}
+
+/// Returns the next match completed by the bytes fed to the searcher so far.
+fn next_match(searcher: &mut Searcher<'_>, ended: bool) -> Option<Range<u64>> {
+    if ended {
+        searcher.finish().next()
+    } else {
+        searcher.feed(&[]).next()
+    }
+}
+
+impl<S: Stream<Item = u8> + Unpin> Stream for MatchesStream<'_, S> {
+    type Item = Range<u64>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(mat) = next_match(&mut this.searcher, this.ended) {
+                return Poll::Ready(Some(mat));
+            }
+            if this.ended {
+                return Poll::Ready(None);
+            }
+
+            // Collect the bytes which are ready, waiting only if there are none.
+            let mut chunk = [0; CHUNK_SIZE];
+            let mut len = 0;
+            while len < chunk.len() {
+                match Pin::new(&mut this.stream).poll_next(cx) {
+                    Poll::Ready(Some(b)) => {
+                        chunk[len] = b;
+                        len += 1;
+                    }
+                    Poll::Ready(None) => {
+                        this.ended = true;
+                        break;
+                    }
+                    Poll::Pending if len == 0 => return Poll::Pending,
+                    Poll::Pending => break,
+                }
+            }
+
+            // The matches are taken one by one above.
+            let _ = this.searcher.feed(&chunk[..len]);
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncReaderMatches<'_, R> {
+    type Item = io::Result<Range<u64>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.failed {
+                return Poll::Ready(None);
+            }
+            if let Some(mat) = next_match(&mut this.searcher, this.ended) {
+                return Poll::Ready(Some(Ok(mat)));
+            }
+            if this.ended {
+                return Poll::Ready(None);
+            }
+
+            let bytes = match ready!(Pin::new(&mut this.reader).poll_fill_buf(cx)) {
+                Ok(bytes) => bytes,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    // Matches in progress might have been cut short, so drop them.
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            };
+
+            if bytes.is_empty() {
+                this.ended = true;
+                continue;
+            }
+
+            let len = bytes.len();
+            let _ = this.searcher.feed(bytes);
+            Pin::new(&mut this.reader).consume(len);
+        }
+    }
+}
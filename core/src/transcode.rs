@@ -0,0 +1,196 @@
+//! Searching haystacks of characters or UTF-16 code units, encoded to UTF-8 on the fly.
+
+use std::{
+    char::{decode_utf16, DecodeUtf16, DecodeUtf16Error, REPLACEMENT_CHARACTER},
+    iter::Map,
+    ops::Range,
+};
+
+use crate::{Matches, Step};
+
+/// An iterator over the matches in a haystack of characters, with ranges of character indices,
+/// see [Regex::matches_chars](crate::Regex::matches_chars).
+#[derive(Debug)]
+pub struct CharMatches<'r, I: Iterator<Item = char>> {
+    inner: Transcoded<'r, Utf8<I>>,
+}
+
+/// An iterator over the matches in a haystack of UTF-16 code units, with ranges of code unit indices,
+/// see [Regex::matches_utf16](crate::Regex::matches_utf16).
+#[derive(Debug)]
+pub struct Utf16Matches<'r, I: Iterator<Item = u16>> {
+    inner: Transcoded<'r, Utf8<Utf16Chars<I>>>,
+}
+
+type Utf16Chars<I> = Map<DecodeUtf16<I>, fn(Result<char, DecodeUtf16Error>) -> char>;
+
+/// The matches in a UTF-8 encoded haystack, converted to indices of the original units.
+#[derive(Debug)]
+struct Transcoded<'r, Haystack: Iterator<Item = u8>> {
+    matches: Matches<'r, Haystack>,
+    counter: Counter,
+}
+
+/// Counts the units of the UTF-8 encoded bytes seen so far.
+#[derive(Debug)]
+pub(crate) struct Counter {
+    /// The number of units of the characters started so far.
+    units: usize,
+    /// How many units the character starting with a byte was encoded in.
+    width: fn(u8) -> usize,
+    /// The number of bytes of the last character which weren't counted yet.
+    pending: usize,
+}
+
+/// The UTF-8 encoding of an iterator over characters.
+#[derive(Debug)]
+pub(crate) struct Utf8<I> {
+    chars: I,
+    buf: [u8; 4],
+    /// The index of the next byte in `buf`.
+    pos: usize,
+    /// The length of the encoded character in `buf`.
+    len: usize,
+}
+
+impl<I: Iterator<Item = char>> Utf8<I> {
+    pub(crate) fn new(chars: I) -> Utf8<I> {
+        Utf8 {
+            chars,
+            buf: [0; 4],
+            pos: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for Utf8<I> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.len {
+            self.len = self.chars.next()?.encode_utf8(&mut self.buf).len();
+            self.pos = 0;
+        }
+
+        let b = self.buf[self.pos];
+        self.pos += 1;
+        Some(b)
+    }
+}
+
+impl<'r, Haystack: Iterator<Item = u8>> Transcoded<'r, Haystack> {
+    fn new(mut matches: Matches<'r, Haystack>, width: fn(u8) -> usize) -> Self {
+        // Stop whenever bytes are dropped, so every byte can be counted.
+        matches.stop_when_skipped = true;
+        matches.keep_match_bytes();
+
+        Transcoded {
+            matches,
+            counter: Counter::new(width),
+        }
+    }
+
+    /// Returns the next match which starts and ends on character boundaries.
+    fn next_match(&mut self) -> Option<Range<usize>> {
+        loop {
+            match self.matches.step() {
+                Step::Match(mat) => {
+                    let input = &self.matches.input;
+                    for (_, b) in input.window().take_while(|&(i, _)| i < mat.start) {
+                        self.counter.count(b);
+                    }
+                    let start = self.counter.boundary();
+                    for (_, b) in input.window_from(mat.start) {
+                        self.counter.count(b);
+                    }
+
+                    if let (Some(start), Some(end)) = (start, self.counter.boundary()) {
+                        return Some(start..end);
+                    }
+                }
+                Step::Skipped => {
+                    for (_, b) in self.matches.input.window() {
+                        self.counter.count(b);
+                    }
+                }
+                Step::Done => return None,
+            }
+        }
+    }
+}
+
+impl Counter {
+    /// Creates a counter where the character starting with a byte is `width` units long.
+    pub(crate) fn new(width: fn(u8) -> usize) -> Counter {
+        Counter {
+            units: 0,
+            width,
+            pending: 0,
+        }
+    }
+
+    pub(crate) fn count(&mut self, b: u8) {
+        if self.pending > 0 {
+            self.pending -= 1;
+            return;
+        }
+
+        self.units += (self.width)(b);
+        self.pending = utf8_len(b) - 1;
+    }
+
+    /// Returns the number of units so far, if the bytes counted end on a character boundary.
+    pub(crate) fn boundary(&self) -> Option<usize> {
+        (self.pending == 0).then_some(self.units)
+    }
+}
+
+/// The length of the UTF-8 sequence starting with `b`.
+fn utf8_len(b: u8) -> usize {
+    match b {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
+
+impl<'r, I: Iterator<Item = char>> CharMatches<'r, I> {
+    pub(crate) fn new(matches: Matches<'r, Utf8<I>>) -> CharMatches<'r, I> {
+        CharMatches {
+            inner: Transcoded::new(matches, |_| 1),
+        }
+    }
+}
+
+impl<'r, I: Iterator<Item = u16>> Utf16Matches<'r, I> {
+    pub(crate) fn new(matches: Matches<'r, Utf8<Utf16Chars<I>>>) -> Utf16Matches<'r, I> {
+        Utf16Matches {
+            // Only characters encoded in 4 bytes need a surrogate pair.
+            inner: Transcoded::new(matches, |b| if b >= 0xf0 { 2 } else { 1 }),
+        }
+    }
+}
+
+/// Decodes UTF-16, replacing unpaired surrogates with [REPLACEMENT_CHARACTER].
+pub(crate) fn utf16_chars<I: Iterator<Item = u16>>(units: I) -> Utf16Chars<I> {
+    decode_utf16(units).map(|c| c.unwrap_or(REPLACEMENT_CHARACTER))
+}
+
+impl<I: Iterator<Item = char>> Iterator for CharMatches<'_, I> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_match()
+    }
+}
+
+impl<I: Iterator<Item = u16>> Iterator for Utf16Matches<'_, I> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_match()
+    }
+}
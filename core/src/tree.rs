@@ -0,0 +1,101 @@
+//! Searching haystacks stored as trees of chunks, like ropes.
+
+use core::{iter::Rev, slice};
+
+/// A haystack stored in chunks which can be walked in both directions, like a rope,
+/// see [Regex::matches_tree](crate::Regex::matches_tree).
+pub trait ChunkTree {
+    /// An iterator over the chunks, from first to last.
+    type Forward<'a>: Iterator<Item = &'a [u8]>
+    where
+        Self: 'a;
+
+    /// An iterator over the chunks, from last to first.
+    type Backward<'a>: Iterator<Item = &'a [u8]>
+    where
+        Self: 'a;
+
+    /// Returns the chunks, from first to last.
+    fn chunks(&self) -> Self::Forward<'_>;
+
+    /// Returns the chunks, from last to first.
+    fn rev_chunks(&self) -> Self::Backward<'_>;
+
+    /// The length of the haystack in bytes.
+    fn len(&self) -> usize;
+
+    /// Returns whether the haystack is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: AsRef<[u8]>> ChunkTree for [T] {
+    type Forward<'a>
+        = core::iter::Map<slice::Iter<'a, T>, fn(&'a T) -> &'a [u8]>
+    where
+        T: 'a;
+
+    type Backward<'a>
+        = core::iter::Map<Rev<slice::Iter<'a, T>>, fn(&'a T) -> &'a [u8]>
+    where
+        T: 'a;
+
+    fn chunks(&self) -> Self::Forward<'_> {
+        self.iter().map(T::as_ref)
+    }
+
+    fn rev_chunks(&self) -> Self::Backward<'_> {
+        self.iter().rev().map(T::as_ref)
+    }
+
+    fn len(&self) -> usize {
+        self.iter().map(|chunk| chunk.as_ref().len()).sum()
+    }
+}
+
+#[cfg(feature = "ropey")]
+impl<'r> ChunkTree for ropey::RopeSlice<'r> {
+    type Forward<'a>
+        = core::iter::Map<ropey::iter::Chunks<'a>, fn(&'a str) -> &'a [u8]>
+    where
+        Self: 'a;
+
+    type Backward<'a>
+        = core::iter::Map<ropey::iter::Chunks<'a>, fn(&'a str) -> &'a [u8]>
+    where
+        Self: 'a;
+
+    fn chunks(&self) -> Self::Forward<'_> {
+        ropey::RopeSlice::chunks(self).map(str::as_bytes)
+    }
+
+    fn rev_chunks(&self) -> Self::Backward<'_> {
+        let (chunks, ..) = self.chunks_at_byte(self.len_bytes());
+        chunks.reversed().map(str::as_bytes)
+    }
+
+    fn len(&self) -> usize {
+        self.len_bytes()
+    }
+}
+
+#[cfg(feature = "ropey")]
+impl ChunkTree for ropey::Rope {
+    type Forward<'a> = <ropey::RopeSlice<'a> as ChunkTree>::Forward<'a>;
+
+    type Backward<'a> = <ropey::RopeSlice<'a> as ChunkTree>::Backward<'a>;
+
+    fn chunks(&self) -> Self::Forward<'_> {
+        ropey::Rope::chunks(self).map(str::as_bytes)
+    }
+
+    fn rev_chunks(&self) -> Self::Backward<'_> {
+        let (chunks, ..) = self.chunks_at_byte(self.len_bytes());
+        chunks.reversed().map(str::as_bytes)
+    }
+
+    fn len(&self) -> usize {
+        self.len_bytes()
+    }
+}
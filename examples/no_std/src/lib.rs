@@ -0,0 +1,11 @@
+//! A `no_std` crate using `regex!`, built by `external::cargo_check_no_std_example`.
+#![no_std]
+
+use core::ops::Range;
+
+use hotsauce::regex;
+
+/// Returns where the first number in the haystack is.
+pub fn first_number(hay: &[u8]) -> Option<Range<usize>> {
+    regex!("[0-9]+").matches(hay.iter().copied()).next()
+}
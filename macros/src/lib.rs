@@ -0,0 +1,69 @@
+//! Compile-time regexes for [hotsauce](https://docs.rs/hotsauce),
+//! re-exported by its `macros` feature.
+
+use hotsauce_core::Regex;
+use proc_macro::TokenStream;
+use proc_macro2::Literal;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// Compiles a regex at compile time, evaluating to a `&'static hotsauce::Regex`.
+///
+/// The DFAs are built while compiling and embedded in the binary,
+/// so using the regex the first time only loads them, without determinizing anything.
+/// Invalid regexes are reported as compile errors.
+/// The DFAs store pointer-sized state IDs, so only targets with the pointer width of the compiling
+/// machine are supported, others fail to compile.
+///
+/// ```rust
+/// use hotsauce::regex;
+///
+/// let mat = regex!("[0-9]+").matches("abc 123".bytes()).next();
+/// assert_eq!(Some(4..7), mat);
+/// ```
+///
+/// ```rust,compile_fail
+/// let regex = hotsauce::regex!("(unclosed");
+/// ```
+#[proc_macro]
+pub fn regex(input: TokenStream) -> TokenStream {
+    let pattern = parse_macro_input!(input as LitStr);
+
+    let regex = match Regex::new(&pattern.value()) {
+        Ok(regex) => regex,
+        Err(err) => {
+            return syn::Error::new(pattern.span(), err)
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    // The macro runs on the host, so embed the regex in both byte orders and let the target pick.
+    // State IDs are `usize`, so targets with another pointer width can't load either.
+    let pointer_width = (8 * std::mem::size_of::<usize>()).to_string();
+    let unsupported = format!("`regex!` only supports {pointer_width}-bit targets");
+    let little = regex.to_bytes_little_endian();
+    let big = regex.to_bytes_big_endian();
+    let (little_len, big_len) = (little.len(), big.len());
+    let (little, big) = (Literal::byte_string(&little), Literal::byte_string(&big));
+
+    quote! {{
+        #[repr(C, align(8))]
+        struct Aligned<B>(B);
+
+        #[cfg(all(target_pointer_width = #pointer_width, target_endian = "little"))]
+        static BYTES: Aligned<[u8; #little_len]> = Aligned(*#little);
+        #[cfg(all(target_pointer_width = #pointer_width, target_endian = "big"))]
+        static BYTES: Aligned<[u8; #big_len]> = Aligned(*#big);
+        #[cfg(not(target_pointer_width = #pointer_width))]
+        ::core::compile_error!(#unsupported);
+        // Only the error above is reported, not the missing bytes.
+        #[cfg(not(target_pointer_width = #pointer_width))]
+        static BYTES: Aligned<[u8; 0]> = Aligned([]);
+
+        // SAFETY: the bytes were serialized for this target by the same version of hotsauce.
+        static REGEX: ::hotsauce::StaticRegex = unsafe { ::hotsauce::StaticRegex::new(&BYTES.0) };
+        REGEX.get()
+    }}
+    .into()
+}
@@ -0,0 +1,85 @@
+use std::io::{self, BufReader, Cursor, Read};
+
+use hotsauce::Regex;
+
+#[test]
+fn matches_chunks() {
+    let regex = Regex::new(r"[0-9]+|ab+c").unwrap();
+    let hay = "x 123 abbbc 4 abbd 56";
+    let expected = regex.matches(hay.bytes()).collect::<Vec<_>>();
+
+    for size in 1..=hay.len() {
+        let chunks = hay.as_bytes().chunks(size);
+        assert_eq!(regex.matches_chunks(chunks).collect::<Vec<_>>(), expected);
+    }
+
+    let chunks = vec![
+        vec![],
+        b"x 12".to_vec(),
+        vec![],
+        b"3 abbbc 4 abbd 56".to_vec(),
+    ];
+    assert_eq!(regex.matches_chunks(chunks).collect::<Vec<_>>(), expected);
+    assert_eq!(regex.matches_chunks(Vec::<&str>::new()).next(), None);
+}
+
+#[test]
+fn long_chunks() {
+    // The chunks are searched a slice at a time, which mustn't change the matches.
+    let regex = Regex::new(r"[0-9]+|ab+c|x{300}y").unwrap();
+    let hay = ("x".repeat(5000) + "123 abbbc ").repeat(3) + &"b".repeat(9000) + "c";
+    let expected = regex.matches(hay.bytes()).collect::<Vec<_>>();
+    assert_eq!(expected.len(), 6);
+
+    for size in [1000, 4095, 4096, 4097, 10_000] {
+        let chunks = hay.as_bytes().chunks(size);
+        assert_eq!(regex.matches_chunks(chunks).collect::<Vec<_>>(), expected);
+
+        let reader = BufReader::with_capacity(size, hay.as_bytes());
+        let actual = regex.matches_reader(reader).collect::<io::Result<Vec<_>>>();
+        let actual = actual
+            .unwrap()
+            .into_iter()
+            .map(|mat| mat.start as usize..mat.end as usize);
+        assert_eq!(actual.collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn matches_reader() {
+    let regex = Regex::new(r"\w+").unwrap();
+    let hay = "héllo wörld, how are you?";
+    let expected = regex.matches_from(hay.bytes(), 0).collect::<Vec<_>>();
+
+    for capacity in [1, 2, 3, 7, 64] {
+        let reader = BufReader::with_capacity(capacity, hay.as_bytes());
+        let actual = regex.matches_reader(reader).collect::<io::Result<Vec<_>>>();
+        assert_eq!(actual.unwrap(), expected);
+    }
+}
+
+/// Fails after reading the bytes it was given.
+struct Failing(Cursor<&'static [u8]>);
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(io::Error::other("connection reset")),
+            n => Ok(n),
+        }
+    }
+}
+
+#[test]
+fn reader_error() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let reader = BufReader::with_capacity(4, Failing(Cursor::new(b"12 34 56")));
+    let mut matches = regex.matches_reader(reader);
+
+    assert_eq!(matches.next().unwrap().unwrap(), 0..2);
+    assert_eq!(matches.next().unwrap().unwrap(), 3..5);
+    // The last match might continue, so it's dropped.
+    let err = matches.next().unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "connection reset");
+    assert!(matches.next().is_none());
+}
@@ -0,0 +1,101 @@
+use hotsauce::{DfaKind, Error, MatchKind, RegexBuilder, StateIdWidth};
+
+const WIDTHS: [StateIdWidth; 4] = [
+    StateIdWidth::U8,
+    StateIdWidth::U16,
+    StateIdWidth::U32,
+    StateIdWidth::Usize,
+];
+
+fn builder(sparse: bool, width: StateIdWidth) -> RegexBuilder {
+    let mut builder = RegexBuilder::new();
+    // Premultiplied state IDs rarely fit into narrow ones.
+    builder
+        .dfa_kind(DfaKind::Dense)
+        .premultiply(false)
+        .sparse(sparse)
+        .state_id_width(width);
+    builder
+}
+
+#[test]
+fn same_matches() {
+    let cases = [
+        ("[a-z]+[0-9]*", "abc12 de 3 f"),
+        ("a|abc", "abcabc xa"),
+        ("x*", "axxb"),
+    ];
+
+    for (pat, hay) in cases {
+        for kind in [MatchKind::LeftmostFirst, MatchKind::LeftmostLongest] {
+            let dense = RegexBuilder::new().match_kind(kind).build(pat).unwrap();
+            let expected = dense.matches(hay.bytes()).collect::<Vec<_>>();
+            let expected_rev = dense.rmatches(hay.bytes().rev()).collect::<Vec<_>>();
+
+            for sparse in [false, true] {
+                for width in WIDTHS {
+                    let regex = builder(sparse, width).match_kind(kind).build(pat).unwrap();
+                    assert_eq!(regex.matches(hay.bytes()).collect::<Vec<_>>(), expected);
+                    assert_eq!(
+                        regex.rmatches(hay.bytes().rev()).collect::<Vec<_>>(),
+                        expected_rev
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn too_many_states() {
+    let err = builder(false, StateIdWidth::U8)
+        .build("[a-z]+[0-9]{300}")
+        .unwrap_err();
+    assert!(matches!(err, Error::Automata(_)));
+
+    let regex = builder(false, StateIdWidth::U16)
+        .build("[a-z]+[0-9]{300}")
+        .unwrap();
+    let hay = format!("ab{}", "0".repeat(300));
+    assert_eq!(regex.matches(hay.bytes()).next(), Some(0..302));
+}
+
+#[test]
+fn memory_usage() {
+    let pat = r"\w+";
+    let dense = builder(false, StateIdWidth::Usize).build(pat).unwrap();
+    let narrow = builder(false, StateIdWidth::U32).build(pat).unwrap();
+    let sparse = builder(true, StateIdWidth::U32).build(pat).unwrap();
+
+    assert!(narrow.memory_usage() < dense.memory_usage());
+    assert!(sparse.memory_usage() < narrow.memory_usage());
+
+    let hay = "naïve ßtraße 42";
+    assert_eq!(
+        sparse.matches(hay.bytes()).collect::<Vec<_>>(),
+        dense.matches(hay.bytes()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn dfa_size_limit() {
+    let limit = builder(false, StateIdWidth::Usize)
+        .build(r"\w+")
+        .unwrap()
+        .memory_usage()
+        / 2;
+
+    let err = builder(false, StateIdWidth::Usize)
+        .dfa_size_limit(limit)
+        .lazy_cache_size(1 << 10)
+        .build(r"\w+")
+        .unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { .. }));
+
+    // Only the size of the sparse DFAs counts.
+    builder(true, StateIdWidth::U32)
+        .dfa_size_limit(limit)
+        .lazy_cache_size(1 << 10)
+        .build(r"\w+")
+        .unwrap();
+}
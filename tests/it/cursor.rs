@@ -0,0 +1,112 @@
+use hotsauce::{Regex, RegexBuilder, SliceCursor};
+
+fn next(regex: &Regex, hay: &str, position: usize, wrap: bool) -> Option<std::ops::Range<u64>> {
+    let mut cursor = SliceCursor::new(hay.as_bytes(), position);
+    regex.find_next(&mut cursor, wrap)
+}
+
+fn prev(regex: &Regex, hay: &str, position: usize, wrap: bool) -> Option<std::ops::Range<u64>> {
+    let mut cursor = SliceCursor::new(hay.as_bytes(), position);
+    regex.find_prev(&mut cursor, wrap)
+}
+
+#[test]
+fn find_next() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let hay = "12 345 6";
+
+    assert_eq!(next(&regex, hay, 0, false), Some(0..2));
+    assert_eq!(next(&regex, hay, 2, false), Some(3..6));
+    assert_eq!(next(&regex, hay, 3, false), Some(3..6));
+    assert_eq!(next(&regex, hay, 4, false), Some(4..6));
+    assert_eq!(next(&regex, hay, 8, false), None);
+    assert_eq!(next(&regex, hay, 8, true), Some(0..2));
+}
+
+#[test]
+fn find_prev() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let hay = "12 345 6";
+
+    assert_eq!(prev(&regex, hay, 8, false), Some(7..8));
+    assert_eq!(prev(&regex, hay, 7, false), Some(3..6));
+    // The match around the cursor isn't cut short.
+    assert_eq!(prev(&regex, hay, 5, false), Some(3..6));
+    assert_eq!(prev(&regex, hay, 3, false), Some(0..2));
+    assert_eq!(prev(&regex, hay, 0, false), None);
+    assert_eq!(prev(&regex, hay, 0, true), Some(7..8));
+}
+
+#[test]
+fn look_around_at_cursor() {
+    let regex = Regex::new(r"\bhey\b").unwrap();
+    let hay = "hey they heyo hey";
+
+    assert_eq!(next(&regex, hay, 1, false), Some(14..17));
+    assert_eq!(prev(&regex, hay, 12, false), Some(0..3));
+    assert_eq!(prev(&regex, hay, 17, false), Some(14..17));
+}
+
+#[test]
+fn empty_matches() {
+    let regex = Regex::new("x*").unwrap();
+    let hay = "axxb";
+
+    assert_eq!(next(&regex, hay, 0, false), Some(0..0));
+    assert_eq!(next(&regex, hay, 2, false), Some(2..3));
+    assert_eq!(prev(&regex, hay, 1, false), Some(0..0));
+    assert_eq!(prev(&regex, hay, 3, false), Some(1..3));
+}
+
+#[test]
+fn leftmost_longest() {
+    let regex = RegexBuilder::new()
+        .match_kind(hotsauce::MatchKind::LeftmostLongest)
+        .build("a|abc")
+        .unwrap();
+    let hay = "abc abc";
+
+    assert_eq!(next(&regex, hay, 1, true), Some(4..7));
+    assert_eq!(prev(&regex, hay, 5, false), Some(4..7));
+}
+
+#[test]
+fn cursor_is_restored() {
+    use hotsauce::Cursor;
+
+    let regex = Regex::new("b").unwrap();
+    let mut cursor = SliceCursor::new(b"abc", 2);
+    regex.find_prev(&mut cursor, true);
+    regex.find_next(&mut cursor, true);
+    assert_eq!(cursor.position(), 2);
+}
+
+#[test]
+fn match_around_cursor() {
+    // The part of these matches before the cursor doesn't match on its own.
+    let regex = Regex::new("abc").unwrap();
+    assert_eq!(prev(&regex, "abc abc", 5, false), Some(4..7));
+
+    let regex = Regex::new("ab|b").unwrap();
+    assert_eq!(prev(&regex, "xab", 2, false), Some(1..3));
+    assert_eq!(prev(&regex, "xab", 1, false), None);
+    assert_eq!(prev(&regex, "xab", 1, true), Some(1..3));
+
+    let regex = Regex::new(r"\bhey\b").unwrap();
+    let hay = "hey they heyo hey";
+    assert_eq!(prev(&regex, hay, 16, false), Some(14..17));
+    assert_eq!(prev(&regex, hay, 14, false), Some(0..3));
+}
+
+#[test]
+fn cursor_inside_character() {
+    let regex = Regex::new("[0-9]+|ö").unwrap();
+    let hay = "é 12 ö 3";
+
+    // The search starts on a continuation byte, which mustn't end it.
+    assert_eq!(next(&regex, hay, 1, false), Some(3..5));
+    assert_eq!(next(&regex, hay, 7, false), Some(9..10));
+    assert_eq!(next(&regex, hay, 7, true), Some(9..10));
+    assert_eq!(prev(&regex, hay, 7, false), Some(6..8));
+    assert_eq!(prev(&regex, hay, 1, true), Some(9..10));
+}
@@ -0,0 +1,297 @@
+//! Comparing matches with the `regex` crate.
+
+use expect_test::expect;
+use hotsauce::{DfaKind, MatchKind, RegexBuilder, StateIdWidth};
+
+const PATTERNS: &[&str] = &[
+    "a*b",
+    "(a|b)*c",
+    "ab|b",
+    "a+ab",
+    "(ab)+c",
+    "a(b|c)*d",
+    "(foo|foobar)baz",
+    "[a-d]+ d",
+    r"(?-u:\w+)",
+    "x*",
+    "b*",
+    "(a|ab)(c|bcd)",
+    "é+",
+    "[^a ]+",
+];
+
+/// Builders for every kind of automaton a regex without look-around can be searched with.
+fn builders() -> Vec<(&'static str, RegexBuilder)> {
+    let mut lazy = RegexBuilder::new();
+    lazy.dfa_kind(DfaKind::Lazy);
+    let mut thrashing = RegexBuilder::new();
+    thrashing.dfa_kind(DfaKind::Lazy).lazy_cache_size(0);
+    let mut sparse = RegexBuilder::new();
+    sparse.sparse(true);
+    let mut narrow = RegexBuilder::new();
+    narrow.state_id_width(StateIdWidth::U32);
+
+    vec![
+        ("dense", RegexBuilder::new()),
+        ("lazy", lazy),
+        ("thrashing", thrashing),
+        ("sparse", sparse),
+        ("u32", narrow),
+    ]
+}
+
+/// Pseudo-random haystacks over a small alphabet, so the patterns match often.
+/// The alphabet includes the bytes of `é` and a byte which is never valid UTF-8.
+fn haystacks() -> Vec<Vec<u8>> {
+    let mut seed = 0x2545_f491_u32;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    let mut haystacks = [
+        "",
+        "ab",
+        "aaab",
+        "ababc",
+        "xaaab",
+        "abcd d",
+        "foobarbaz",
+        "é aé",
+    ]
+    .map(|hay| hay.as_bytes().to_vec())
+    .to_vec();
+    haystacks.push([&b"a".repeat(1000)[..], b"b"].concat());
+    haystacks.push([&b"ab".repeat(500)[..], b"c"].concat());
+    haystacks.push(b"\xa9 a \xff a".to_vec());
+    for len in (1..600).step_by(3) {
+        let hay = (0..len)
+            .map(|_| b"abcd xfo\xc3\xa9\xff"[random() as usize % 11])
+            .collect();
+        haystacks.push(hay);
+    }
+    haystacks
+}
+
+#[test]
+fn matches() {
+    for pattern in PATTERNS {
+        let expected_regex = regex::bytes::Regex::new(pattern).unwrap();
+        for (name, builder) in builders() {
+            let regex = builder.build(pattern).unwrap();
+            for hay in haystacks() {
+                let expected = expected_regex
+                    .find_iter(&hay)
+                    .map(|mat| mat.range())
+                    .collect::<Vec<_>>();
+                let actual = regex.matches(hay.iter().copied()).collect::<Vec<_>>();
+                assert_eq!(
+                    actual,
+                    expected,
+                    "{:?} with {} on {:?}",
+                    pattern,
+                    name,
+                    String::from_utf8_lossy(&hay),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn captures() {
+    for pattern in PATTERNS {
+        let expected_regex = regex::bytes::Regex::new(pattern).unwrap();
+        let regex = RegexBuilder::new().build(pattern).unwrap();
+        for hay in haystacks() {
+            let expected = expected_regex
+                .captures_iter(&hay)
+                .map(|caps| {
+                    (0..caps.len())
+                        .map(|i| caps.get(i).map(|mat| mat.range()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let actual = regex
+                .captures(hay.iter().copied())
+                .map(|caps| (0..regex.captures_len()).map(|i| caps.get(i)).collect())
+                .collect::<Vec<Vec<_>>>();
+            assert_eq!(actual, expected, "{:?} on {:?}", pattern, hay);
+        }
+    }
+}
+
+#[test]
+fn leftmost_longest() {
+    // The `regex` crate has no leftmost-longest semantics to compare with.
+    let cases = [
+        ("a*b", "ab"),
+        ("a*b", "aaab"),
+        ("(a|b)*c", "ababc"),
+        ("a|ab|abc", "xabcab"),
+        ("(a|ab)(c|bcd)", "abcd"),
+    ];
+
+    let expect = expect![[r#"
+        [
+            [
+                0..2,
+            ],
+            [
+                0..4,
+            ],
+            [
+                0..5,
+            ],
+            [
+                1..4,
+                4..6,
+            ],
+            [
+                0..4,
+            ],
+        ]
+    "#]];
+
+    let mut dense = RegexBuilder::new();
+    dense.match_kind(MatchKind::LeftmostLongest);
+    let actual = cases
+        .iter()
+        .map(|&(pattern, hay)| {
+            let regex = dense.build(pattern).unwrap();
+            regex.matches(hay.bytes()).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+
+    for (name, mut builder) in builders() {
+        builder.match_kind(MatchKind::LeftmostLongest);
+        for (&(pattern, hay), expected) in cases.iter().zip(&actual) {
+            let regex = builder.build(pattern).unwrap();
+            let matches = regex.matches(hay.bytes()).collect::<Vec<_>>();
+            assert_eq!(
+                &matches, expected,
+                "{:?} with {} on {:?}",
+                pattern, name, hay
+            );
+        }
+    }
+}
+
+#[test]
+fn replace_all() {
+    for pattern in PATTERNS {
+        let expected_regex = regex::bytes::Regex::new(pattern).unwrap();
+        for (name, builder) in builders() {
+            let regex = builder.build(pattern).unwrap();
+            for hay in haystacks() {
+                let expected = expected_regex.replace_all(&hay, &b"<$0>"[..]);
+                let actual = regex
+                    .replace_all(hay.iter().copied(), "<$0>")
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    String::from_utf8_lossy(&actual),
+                    String::from_utf8_lossy(&expected),
+                    "{:?} with {}",
+                    pattern,
+                    name,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn split() {
+    for pattern in PATTERNS {
+        let expected_regex = regex::bytes::Regex::new(pattern).unwrap();
+        let regex = RegexBuilder::new().build(pattern).unwrap();
+        for hay in haystacks() {
+            let expected = expected_regex.split(&hay).collect::<Vec<_>>();
+            let actual = regex
+                .split(hay.iter().copied())
+                .map(|range| &hay[range])
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected, "{:?}", pattern);
+        }
+    }
+}
+
+#[test]
+fn rmatches() {
+    // Patterns together with their reversal, which finds the same matches on the reversed haystack.
+    let patterns = [
+        ("a*b", "ba*"),
+        ("(a|b)*c", "c(a|b)*"),
+        ("a+ab", "baa+"),
+        ("a(b|c)*d", "d(b|c)*a"),
+        ("[a-d]+ d", "d [a-d]+"),
+    ];
+
+    for (pattern, reversed) in patterns {
+        let expected_regex = regex::bytes::Regex::new(reversed).unwrap();
+        for (name, builder) in builders() {
+            let regex = builder.build(pattern).unwrap();
+            for mut hay in haystacks() {
+                hay.reverse();
+                let expected = expected_regex
+                    .find_iter(&hay)
+                    .map(|mat| mat.range())
+                    .collect::<Vec<_>>();
+                let actual = regex.rmatches(hay.iter().copied()).collect::<Vec<_>>();
+                assert_eq!(actual, expected, "{:?} with {}", pattern, name);
+            }
+        }
+    }
+}
+
+#[test]
+fn look_around() {
+    // Patterns together with their reversal, which finds the same matches on the reversed haystack.
+    let patterns = [
+        (r"(?-u:\b)ab", r"ba(?-u:\b)"),
+        (r"(?-u:\b)[a-d]+(?-u:\b)", r"(?-u:\b)[a-d]+(?-u:\b)"),
+        (r"(?m)^$", r"(?m)^$"),
+        (r"(?m)^a+", r"(?m)a+$"),
+        (r"(?m)[a-d ]+$", r"(?m)^[a-d ]+"),
+        (r"(?-u:\b)x*", r"x*(?-u:\b)"),
+        (r"^a|b$", r"a$|^b"),
+    ];
+
+    let mut thrashing = RegexBuilder::new();
+    thrashing.lazy_cache_size(0);
+    let builders = [("default", RegexBuilder::new()), ("thrashing", thrashing)];
+
+    for (pattern, reversed) in patterns {
+        let expected_regex = regex::bytes::Regex::new(pattern).unwrap();
+        let expected_reversed = regex::bytes::Regex::new(reversed).unwrap();
+        for (name, builder) in &builders {
+            let regex = builder.build(pattern).unwrap();
+            for mut hay in haystacks() {
+                let expected = expected_regex
+                    .find_iter(&hay)
+                    .map(|mat| mat.range())
+                    .collect::<Vec<_>>();
+                let actual = regex.matches(hay.iter().copied()).collect::<Vec<_>>();
+                assert_eq!(
+                    actual,
+                    expected,
+                    "{:?} with {} on {:?}",
+                    pattern,
+                    name,
+                    String::from_utf8_lossy(&hay),
+                );
+
+                hay.reverse();
+                let expected = expected_reversed
+                    .find_iter(&hay)
+                    .map(|mat| mat.range())
+                    .collect::<Vec<_>>();
+                let actual = regex.rmatches(hay.iter().copied()).collect::<Vec<_>>();
+                assert_eq!(actual, expected, "{:?} backwards with {}", pattern, name);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use encoding_rs::{Encoding, BIG5, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use expect_test::expect;
+use hotsauce::Regex;
+
+/// Encodes a character, which `encoding_rs` only does for encodings other than UTF-16.
+fn encode(c: char, encoding: &'static Encoding) -> Vec<u8> {
+    let units = c.encode_utf16(&mut [0; 2]).to_vec();
+    match encoding.name() {
+        "UTF-16LE" => units.iter().flat_map(|u| u.to_le_bytes()).collect(),
+        "UTF-16BE" => units.iter().flat_map(|u| u.to_be_bytes()).collect(),
+        _ => encoding.encode(&c.to_string()).0.into_owned(),
+    }
+}
+
+/// Searches the encoded haystack, checking the matches are those the `regex` crate finds in the UTF-8 one.
+fn check(pattern: &str, hay: &str, encoding: &'static Encoding, bom: &[u8]) {
+    // The offset of each character boundary in the encoded haystack.
+    let mut encoded = bom.to_vec();
+    let mut offsets = vec![(0, encoded.len())];
+    for (i, c) in hay.char_indices() {
+        encoded.extend(encode(c, encoding));
+        offsets.push((i + c.len_utf8(), encoded.len()));
+    }
+    let offset = |i| offsets.iter().find(|&&(j, _)| j == i).unwrap().1;
+
+    let expected = regex::Regex::new(pattern)
+        .unwrap()
+        .find_iter(hay)
+        .map(|mat| offset(mat.start())..offset(mat.end()))
+        .collect::<Vec<_>>();
+
+    // The BOM has to win over the declared encoding.
+    let declared = if bom.is_empty() {
+        encoding
+    } else {
+        WINDOWS_1252
+    };
+    let regex = Regex::new(pattern).unwrap();
+    let actual = regex
+        .matches_encoded(encoded.iter().copied(), declared)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+}
+
+const HAY: &str = "héllo wörld, ça va? ½ ÿ";
+
+#[test]
+fn windows_1252() {
+    check(r"\w+", HAY, WINDOWS_1252, &[]);
+    check("x*", HAY, WINDOWS_1252, &[]);
+}
+
+#[test]
+fn shift_jis() {
+    let pattern = r"\p{Hiragana}+|[a-z]+";
+    check(pattern, "abc こんにちは def ひらがな", SHIFT_JIS, &[]);
+}
+
+#[test]
+fn bom() {
+    let pattern = r"\w+|^";
+    let hay = "wörld 🌶🌶 ça";
+    check(pattern, hay, UTF_8, b"\xef\xbb\xbf");
+    check(pattern, hay, UTF_16LE, b"\xff\xfe");
+    check(pattern, hay, UTF_16BE, b"\xfe\xff");
+}
+
+#[test]
+fn short_haystacks() {
+    let pattern = "a|^";
+    check(pattern, "", WINDOWS_1252, &[]);
+    check(pattern, "a", WINDOWS_1252, &[]);
+    check(pattern, "", UTF_16LE, b"\xff\xfe");
+    check(pattern, "a", UTF_16LE, b"\xff\xfe");
+}
+
+#[test]
+fn offsets() {
+    let regex = Regex::new(r"\w+").unwrap();
+    let hay = b"\xff\xfe\xe7\x00a\x00 \x00=\x00\x3c\xd8\x36\xdfb\x00";
+
+    let actual = regex
+        .matches_encoded(hay.iter().copied(), WINDOWS_1252)
+        .collect::<Vec<_>>();
+
+    let expect = expect![[r#"
+        [
+            2..6,
+            14..16,
+        ]
+    "#]];
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn malformed() {
+    let regex = Regex::new("\u{fffd}").unwrap();
+    let hay = b"a\xffb\xc3";
+    let actual = regex
+        .matches_encoded(hay.iter().copied(), UTF_8)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, [1..2, 3..4]);
+}
+
+#[test]
+fn malformed_before_char() {
+    // One byte ends the malformed sequence before it and decodes to a character of its own.
+    let find = |pattern, hay: &[u8], encoding| {
+        let regex = Regex::new(pattern).unwrap();
+        let matches = regex.matches_encoded(hay.iter().copied(), encoding);
+        matches.collect::<Vec<_>>()
+    };
+    assert_eq!(find("b", b"a\xe2b", UTF_8), vec![2..3]);
+    assert_eq!(find("\u{fffd}", b"a\xe2b", UTF_8), vec![1..2]);
+    assert_eq!(find("\u{fffd}b", b"a\xe2\x82b\xe2", UTF_8), vec![1..4]);
+    assert_eq!(find("\u{fffd}", b"a\xe2\x82b\xe2", UTF_8), [1..3, 4..5]);
+    assert_eq!(find(" ", b"\x82 ", SHIFT_JIS), vec![1..2]);
+    assert_eq!(
+        find("\u{fffd} |b", b"b\x82 b", SHIFT_JIS),
+        [0..1, 1..3, 3..4]
+    );
+    assert_eq!(find("\u{fffd}", b"\xd8\x00\x00a", UTF_16BE), vec![0..2]);
+    assert_eq!(find("a", b"\xd8\x00\x00a", UTF_16BE), vec![2..4]);
+
+    // Both characters of this Big5 sequence come from the same two bytes.
+    assert_eq!(find("\u{304}", b"a\x88\x62", BIG5), vec![1..3]);
+    assert_eq!(find("\u{ca}", b"a\x88\x62", BIG5), vec![1..3]);
+}
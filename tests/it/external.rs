@@ -39,3 +39,61 @@ fn cargo_deny_check() {
 
     assert!(output.status.success());
 }
+
+#[test]
+fn cargo_check_no_std() {
+    let output = Command::new("cargo")
+        .args(["check", "--lib", "--no-default-features"])
+        .current_dir(project_root())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn cargo_test_no_std() {
+    let output = Command::new("cargo")
+        .args(["test", "--no-default-features", "--test", "no_std"])
+        .current_dir(project_root())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn cargo_check_alloc() {
+    let output = Command::new("cargo")
+        .args([
+            "check",
+            "--lib",
+            "--no-default-features",
+            "--features",
+            "alloc",
+        ])
+        .current_dir(project_root())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn cargo_check_no_std_example() {
+    let output = Command::new("cargo")
+        .args(["check", "--package", "no-std-example"])
+        .current_dir(project_root())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
@@ -0,0 +1,123 @@
+use std::io::{self, BufReader, Read};
+
+use hotsauce::{DfaKind, MatchKind, Regex, RegexBuilder};
+
+fn fallible(hay: &str, error_at: usize) -> impl Iterator<Item = Result<u8, usize>> + '_ {
+    hay.bytes()
+        .enumerate()
+        .map(move |(i, b)| if i == error_at { Err(i) } else { Ok(b) })
+}
+
+#[test]
+fn no_error() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let matches = regex
+        .try_matches(fallible("1 23 456", usize::MAX))
+        .collect::<Result<Vec<_>, _>>();
+    assert_eq!(matches, Ok(vec![0..1, 2..4, 5..8]));
+}
+
+#[test]
+fn error_position() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let hay = "1 23 456";
+
+    let matches = |error_at| {
+        regex
+            .try_matches(fallible(hay, error_at))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(matches(0), [Err(0)]);
+    // The failed byte might have continued the match, so it's dropped.
+    assert_eq!(matches(1), [Err(1)]);
+    assert_eq!(matches(2), [Ok(0..1), Err(2)]);
+    assert_eq!(matches(3), [Ok(0..1), Err(3)]);
+    assert_eq!(matches(5), [Ok(0..1), Ok(2..4), Err(5)]);
+    assert_eq!(matches(7), [Ok(0..1), Ok(2..4), Err(7)]);
+}
+
+#[test]
+fn match_ending_at_error() {
+    let mut lazy = RegexBuilder::new();
+    lazy.dfa_kind(DfaKind::Lazy);
+    let mut longest = RegexBuilder::new();
+    longest.match_kind(MatchKind::LeftmostLongest);
+
+    for builder in [RegexBuilder::new(), lazy] {
+        let matches = |pattern, hay, error_at| {
+            let regex = builder.build(pattern).unwrap();
+            regex
+                .try_matches(fallible(hay, error_at))
+                .collect::<Vec<_>>()
+        };
+
+        // No byte continues these matches, so they don't depend on the failed one.
+        assert_eq!(matches("a b", "a bc", 3), [Ok(0..3), Err(3)]);
+        assert_eq!(matches("ab|abcd", "abcd", 3), [Ok(0..2), Err(3)]);
+        // The failed byte might make the other alternative win.
+        assert_eq!(matches("abcd|ab", "abcd", 3), [Err(3)]);
+    }
+
+    let regex = longest.build("ab|abcd|x").unwrap();
+    let matches = regex.try_matches(fallible("x abcdy", 6));
+    assert_eq!(matches.collect::<Vec<_>>(), [Ok(0..1), Ok(2..6), Err(6)]);
+    let matches = regex.try_matches(fallible("x ab x", 4));
+    assert_eq!(matches.collect::<Vec<_>>(), [Ok(0..1), Err(4)]);
+}
+
+#[test]
+fn thrashing_cache() {
+    let builder = |pattern| {
+        RegexBuilder::new()
+            .dfa_kind(DfaKind::Lazy)
+            .lazy_cache_size(0)
+            .build(pattern)
+            .unwrap()
+    };
+
+    let regex = builder("[a-c]+?");
+    let matches = regex.try_matches(fallible("a", 1)).collect::<Vec<_>>();
+    assert_eq!(matches, [Ok(0..1)]);
+    let regex = builder("(a|ab)(c|bcd)(d*)");
+    let matches = regex.try_matches(fallible("acx", 2)).collect::<Vec<_>>();
+    assert_eq!(matches, [Err(2)]);
+}
+
+#[test]
+fn look_around() {
+    let regex = Regex::new(r"foo\b").unwrap();
+    let matches = regex
+        .try_matches(fallible("foo foo", 7))
+        .collect::<Vec<_>>();
+    assert_eq!(matches, [Ok(0..3), Ok(4..7)]);
+
+    // Whether the second match ends at a word boundary depends on the failed byte.
+    let matches = regex
+        .try_matches(fallible("foo foox", 7))
+        .collect::<Vec<_>>();
+    assert_eq!(matches, [Ok(0..3), Err(7)]);
+}
+
+/// Fails after reading the bytes it was given.
+struct Failing(&'static [u8]);
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(io::Error::other("connection reset")),
+            n => Ok(n),
+        }
+    }
+}
+
+#[test]
+fn read_bytes() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let mut matches = regex.try_matches(BufReader::new(Failing(b"12 34")).bytes());
+
+    assert_eq!(matches.next().unwrap().unwrap(), 0..2);
+    let err = matches.next().unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "connection reset");
+    assert!(matches.next().is_none());
+}
@@ -0,0 +1,90 @@
+use hotsauce::{DfaKind, MatchKind, Regex, RegexBuilder};
+
+const CASES: &[(&str, &str)] = &[
+    ("a|abc", "abc xabcx"),
+    ("foo|bar", "xfoobarfooba"),
+    ("x*", "abxxc"),
+    (
+        r"[a-z]+@[a-z]+\.com",
+        "mail alice@example.com, bob@test.com",
+    ),
+    ("[01]*1[01]{3}", "0011010 2 1000"),
+    ("(a|ab)(c|bcd)", "abcd"),
+    ("", "abc"),
+    ("näive|ü+", "naïve näive üüü"),
+];
+
+fn all_matches(builder: &RegexBuilder, pat: &str, hay: &str) -> Vec<Vec<std::ops::Range<usize>>> {
+    let regex = builder.build(pat).unwrap();
+    vec![
+        regex.matches(hay.bytes()).collect(),
+        regex.rmatches(hay.bytes().rev()).collect(),
+    ]
+}
+
+fn check_same_as_dense(configure: impl Fn(&mut RegexBuilder)) {
+    for kind in [MatchKind::LeftmostFirst, MatchKind::LeftmostLongest] {
+        let mut dense = RegexBuilder::new();
+        dense.match_kind(kind).dfa_kind(DfaKind::Dense);
+        let mut lazy = RegexBuilder::new();
+        lazy.match_kind(kind).dfa_kind(DfaKind::Lazy);
+        configure(&mut lazy);
+
+        for (pat, hay) in CASES {
+            assert_eq!(
+                all_matches(&dense, pat, hay),
+                all_matches(&lazy, pat, hay),
+                "{:?} in {:?} with {:?}",
+                pat,
+                hay,
+                kind,
+            );
+        }
+    }
+}
+
+#[test]
+fn same_as_dense() {
+    check_same_as_dense(|_| {});
+}
+
+#[test]
+fn small_cache() {
+    check_same_as_dense(|builder| {
+        builder.lazy_cache_size(2048);
+    });
+}
+
+#[test]
+fn falls_back_when_thrashing() {
+    check_same_as_dense(|builder| {
+        builder.lazy_cache_size(0);
+    });
+}
+
+#[test]
+fn exponential_dfa() {
+    // The dense DFAs for this would have millions of states.
+    let regex = Regex::new("[01]*1[01]{24}").unwrap();
+    let hay = "2".to_owned() + &"10".repeat(20) + "2";
+
+    let matches = regex.matches(hay.bytes()).collect::<Vec<_>>();
+    assert_eq!(vec![1..40], matches);
+}
+
+#[test]
+fn lazy_captures_and_replace() {
+    let regex = RegexBuilder::new()
+        .dfa_kind(DfaKind::Lazy)
+        .build(r"(\w+)=(\w+)")
+        .unwrap();
+
+    let caps = regex.captures("a=1 bc=23".bytes()).collect::<Vec<_>>();
+    assert_eq!(Some(4..6), caps[1].get(1));
+    assert_eq!(Some(7..9), caps[1].get(2));
+
+    let out = regex
+        .replace_all("a=1 bc=23".bytes(), "$2=$1")
+        .collect::<Vec<_>>();
+    assert_eq!(b"1=a 23=bc".to_vec(), out);
+}
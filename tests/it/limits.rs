@@ -0,0 +1,171 @@
+use hotsauce::{DfaKind, Error, Regex, RegexBuilder};
+
+#[test]
+fn nfa_size_limit() {
+    let err = RegexBuilder::new()
+        .size_limit(1 << 10)
+        .build(r"\w{100}")
+        .unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { limit: 1024 }));
+    assert_eq!(
+        err.to_string(),
+        "compiled regex exceeds size limit of 1024 bytes"
+    );
+
+    let err = RegexBuilder::new()
+        .size_limit(1 << 10)
+        .build_set([r"\w{100}", "a"])
+        .unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { limit: 1024 }));
+
+    // Nested repetitions are cut short instead of compiling billions of states.
+    let err = Regex::new(r"((a{1000}){1000}){1000}").unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { .. }));
+}
+
+#[test]
+fn dfa_size_limit() {
+    let err = RegexBuilder::new()
+        .dfa_kind(DfaKind::Dense)
+        .dfa_size_limit(1 << 10)
+        .build("[a-z]+[0-9]{10}")
+        .unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { limit: 1024 }));
+
+    // The caches of the lazy DFA used instead have to fit as well.
+    let regex = RegexBuilder::new()
+        .dfa_size_limit(1 << 10)
+        .lazy_cache_size(1 << 8)
+        .build("[a-z]+[0-9]{10}")
+        .unwrap();
+    let mat = regex.matches("-- abc0123456789 --".bytes()).next();
+    assert_eq!(mat, Some(3..16));
+}
+
+#[test]
+fn memory_usage() {
+    let dense = RegexBuilder::new()
+        .dfa_kind(DfaKind::Dense)
+        .build("[01]*1[01]{10}")
+        .unwrap();
+    let lazy = RegexBuilder::new()
+        .dfa_kind(DfaKind::Lazy)
+        .build("[01]*1[01]{10}")
+        .unwrap();
+
+    assert!(lazy.memory_usage() > 0);
+    assert!(dense.memory_usage() > lazy.memory_usage());
+}
+
+#[test]
+fn window_limit() {
+    let hay = || "xa".bytes().chain([b'b'; 1000]).chain("z a z".bytes());
+    for kind in [DfaKind::Dense, DfaKind::Lazy] {
+        let regex = RegexBuilder::new().dfa_kind(kind).build("a[^z]*z").unwrap();
+        let matches = regex
+            .matches(hay())
+            .window_limit(Some(10))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, [1..1003, 1004..1007]);
+    }
+
+    // The match is long but found right away, so the NFA only looks for its end.
+    let regex = Regex::new("[0-9]+").unwrap();
+    let hay = [b'1'; 1000].into_iter().chain(" 22".bytes());
+    let matches = regex
+        .matches(hay)
+        .window_limit(Some(10))
+        .collect::<Vec<_>>();
+    assert_eq!(matches, [0..1000, 1001..1003]);
+
+    // Look-around is searched for with the NFA from the start.
+    let regex = Regex::new(r"\ba[^z]*z\b").unwrap();
+    let hay = "a".bytes().chain([b'b'; 1000]).chain("z a z".bytes());
+    let matches = regex
+        .matches(hay)
+        .window_limit(Some(10))
+        .collect::<Vec<_>>();
+    assert_eq!(matches, [0..1002, 1003..1006]);
+
+    // The searcher waits for more bytes with the NFA as well.
+    let regex = Regex::new("a[^z]*z").unwrap();
+    let mut searcher = regex.searcher();
+    let mut matches = searcher.feed(b"xa").collect::<Vec<_>>();
+    for _ in 0..20 {
+        matches.extend(searcher.feed(&[b'b'; 1 << 16]));
+    }
+    matches.extend(searcher.feed(b"z a"));
+    matches.extend(searcher.feed(b" z"));
+    matches.extend(searcher.finish());
+    let len = 20 << 16;
+    assert_eq!(matches, [1..len + 3, len + 4..len + 7]);
+}
+
+#[test]
+fn memory_within_limits() {
+    let (size_limit, dfa_size_limit) = (1 << 20, 1 << 19);
+    for re in [
+        r"\w",
+        r"\w{5}",
+        r"\b\w+\b",
+        "[a-z]+[0-9]{10}",
+        "[01]*1[01]{10}",
+    ] {
+        for kind in [DfaKind::Auto, DfaKind::Dense, DfaKind::Lazy] {
+            let result = RegexBuilder::new()
+                .size_limit(size_limit)
+                .dfa_size_limit(dfa_size_limit)
+                .lazy_cache_size(1 << 16)
+                .dfa_kind(kind)
+                .build(re);
+            match result {
+                Ok(regex) => assert!(
+                    regex.memory_usage() <= size_limit + dfa_size_limit,
+                    "{} with {:?} uses {} bytes",
+                    re,
+                    kind,
+                    regex.memory_usage()
+                ),
+                Err(err) => assert!(matches!(err, Error::SizeLimitExceeded { .. })),
+            }
+        }
+    }
+
+    // Matching what precedes a position takes NFAs of its own, which count as well.
+    let err = RegexBuilder::new()
+        .size_limit(1 << 20)
+        .build(r"\w{5}")
+        .unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { limit: 1048576 }));
+    let err = Regex::new(r"\w{50}").unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { .. }));
+
+    // So do the caches of lazy DFAs.
+    let err = RegexBuilder::new()
+        .dfa_kind(DfaKind::Lazy)
+        .dfa_size_limit(1 << 20)
+        .build("[a-z]+")
+        .unwrap_err();
+    assert!(matches!(err, Error::SizeLimitExceeded { limit: 1048576 }));
+}
+
+#[test]
+fn window_limit_backwards() {
+    let hay = || "xa".bytes().chain([b'b'; 1000]).chain("z a z".bytes());
+    for kind in [DfaKind::Dense, DfaKind::Lazy] {
+        let regex = RegexBuilder::new().dfa_kind(kind).build("a[^z]*z").unwrap();
+        let hay = hay().collect::<Vec<_>>();
+        let matches = regex
+            .rmatches(hay.iter().rev().copied())
+            .window_limit(Some(10))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, [0..3, 4..1006]);
+
+        let matches = regex
+            .rmatches(hay.iter().rev().copied())
+            .with_bytes(4)
+            .map(|mat| mat.map(|mat| mat.range).map_err(|err| err.range))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, [Ok(0..3), Err(4..1006)]);
+    }
+}
@@ -0,0 +1,232 @@
+use expect_test::{expect, Expect};
+use hotsauce::{Regex, RegexBuilder};
+
+use crate::check;
+
+fn check_multi_line(pat: &str, crlf: bool, hay: &str, expect: Expect) {
+    let actual = RegexBuilder::new()
+        .multi_line(true)
+        .crlf(crlf)
+        .build(pat)
+        .unwrap()
+        .matches(hay.bytes())
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn start_and_end_of_text() {
+    let pat = r"^\w+|\w+$";
+    let hay = "foo bar\nbaz";
+
+    let expect = expect![[r#"
+        [
+            0..3,
+            8..11,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn multi_line() {
+    let pat = r"^\w+$";
+    let hay = "foo\nbar baz\n\nqux";
+
+    let expect = expect![[r#"
+        [
+            0..3,
+            13..16,
+        ]
+    "#]];
+
+    check_multi_line(pat, false, hay, expect);
+}
+
+#[test]
+fn multi_line_crlf() {
+    let pat = r"^\w*$";
+    let hay = "foo\r\nbar\rbaz\n";
+
+    let expect = expect![[r#"
+        [
+            0..3,
+            5..8,
+            9..12,
+            13..13,
+        ]
+    "#]];
+
+    check_multi_line(pat, true, hay, expect);
+}
+
+#[test]
+fn multi_line_without_crlf() {
+    let pat = r"^\w+$";
+    let hay = "foo\r\nbar";
+
+    let expect = expect![[r#"
+        [
+            5..8,
+        ]
+    "#]];
+
+    check_multi_line(pat, false, hay, expect);
+}
+
+#[test]
+fn word_boundary() {
+    let pat = r"\bERROR\b";
+    let hay = "ERROR: ERRORS, NOERROR (ERROR)";
+
+    let expect = expect![[r#"
+        [
+            0..5,
+            24..29,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn not_word_boundary() {
+    let pat = r"\Bo\B";
+    let hay = "foo o boot";
+
+    let expect = expect![[r#"
+        [
+            1..2,
+            7..8,
+            8..9,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn not_word_boundary_inside_character() {
+    // Like the regex crate, `\B` doesn't split a character, or match next to invalid UTF-8.
+    let pat = r"\B";
+    let hay = b"\xc3\xa9\xc3\xa9 \xff ";
+
+    let regex = regex::bytes::Regex::new(pat).unwrap();
+    let expected = regex.find_iter(hay).map(|m| m.range()).collect::<Vec<_>>();
+    assert_eq!(expected, [2..2, 7..7]);
+
+    let regex = Regex::new(pat).unwrap();
+    let actual = regex.matches(hay.iter().copied()).collect::<Vec<_>>();
+    assert_eq!(actual, [2..2, 7..7]);
+    let actual = regex
+        .rmatches_exact(hay.iter().copied())
+        .collect::<Vec<_>>();
+    assert_eq!(actual, [7..7, 2..2]);
+}
+
+#[test]
+fn unicode_word_boundary() {
+    let pat = r"\b\w+\b";
+    let hay = "für ünïcode, 日本";
+
+    let expect = expect![[r#"
+        [
+            0..4,
+            5..14,
+            16..22,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn ascii_word_boundary() {
+    let regex = RegexBuilder::new()
+        .unicode(false)
+        .allow_invalid_utf8(true)
+        .build(r"\b\w+\b")
+        .unwrap();
+    let matches = regex.matches("für x".bytes()).collect::<Vec<_>>();
+    assert_eq!(vec![0..1, 3..4, 5..6], matches);
+}
+
+#[test]
+fn empty_look_around_matches() {
+    let pat = r"\b";
+    let hay = "ab cd";
+
+    let expect = expect![[r#"
+        [
+            0..0,
+            2..2,
+            3..3,
+            5..5,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn look_around_backwards() {
+    let regex = Regex::new(r"\bfoo|bar$").unwrap();
+    let hay = "foo xfoo bar";
+    let matches = regex.rmatches(hay.bytes().rev()).collect::<Vec<_>>();
+    assert_eq!(vec![0..3, 9..12], matches);
+}
+
+#[test]
+fn look_around_captures() {
+    let regex = Regex::new(r"\b(\w)(\w*)\b").unwrap();
+    let caps = regex.captures("ab c".bytes()).collect::<Vec<_>>();
+
+    let expect = expect![[r#"
+        [
+            [
+                Some(
+                    0..2,
+                ),
+                Some(
+                    0..1,
+                ),
+                Some(
+                    1..2,
+                ),
+            ],
+            [
+                Some(
+                    3..4,
+                ),
+                Some(
+                    3..4,
+                ),
+                Some(
+                    4..4,
+                ),
+            ],
+        ]
+    "#]];
+    expect.assert_debug_eq(&caps);
+}
+
+#[test]
+fn look_around_captures_after_gap() {
+    // The context of the match lies before the window, which was cleared while skipping bytes.
+    let regex = Regex::new(r"(\w+)=(\d+)|\bfoo\b").unwrap();
+    let caps = regex
+        .captures("a=1 foo bc=23".bytes())
+        .map(|caps| caps.get(0))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![Some(0..3), Some(4..7), Some(8..13)], caps);
+}
+
+#[test]
+fn look_around_replace() {
+    let regex = Regex::new(r"\bcat\b").unwrap();
+    let out = regex
+        .replace_all("cat concat cat.".bytes(), "dog")
+        .collect::<Vec<_>>();
+    assert_eq!(b"dog concat dog.".to_vec(), out);
+}
@@ -0,0 +1,26 @@
+use hotsauce::{regex, Regex};
+
+#[test]
+fn matches_like_runtime_regex() {
+    let pattern = r"(?P<key>\w+)=(\d+)|\bfoo\b";
+    let hay = "a=1 foo bc=23 food";
+
+    let regex: &'static Regex = regex!(r"(?P<key>\w+)=(\d+)|\bfoo\b");
+    let expected = Regex::new(pattern).unwrap();
+
+    assert_eq!(
+        regex.matches(hay.bytes()).collect::<Vec<_>>(),
+        expected.matches(hay.bytes()).collect::<Vec<_>>(),
+    );
+    let caps = regex.captures(hay.bytes()).nth(2).unwrap();
+    assert_eq!(caps.name("key"), Some(8..10));
+}
+
+#[test]
+fn evaluates_to_the_same_regex() {
+    let first = (0..2)
+        .map(|_| regex!("a+") as *const Regex)
+        .collect::<Vec<_>>();
+    assert_eq!(first[0], first[1]);
+    assert_eq!(regex!("a+").matches("baa".bytes()).next(), Some(1..3));
+}
@@ -1,7 +1,30 @@
 use expect_test::{expect, Expect};
-use hotsauce::{Regex, RegexBuilder};
-
+use hotsauce::{MatchKind, Regex, RegexBuilder};
+
+mod chunks;
+mod compact;
+mod cursor;
+mod differential;
+#[cfg(feature = "encoding_rs")]
+mod encoding;
 mod external;
+mod fallible;
+mod lazy;
+mod limits;
+mod look;
+mod macros;
+mod offsets;
+mod owned;
+mod position;
+mod replace;
+mod searcher;
+mod serialize;
+mod set;
+mod split;
+#[cfg(feature = "futures")]
+mod stream;
+mod transcode;
+mod tree;
 
 fn check(pat: &str, hay: &str, expect: Expect) {
     let actual = Regex::new(pat)
@@ -164,3 +187,349 @@ fn case_insensitive() {
 
     expect.assert_debug_eq(&actual);
 }
+
+#[test]
+fn leftmost_first() {
+    let pat = "a|abc";
+    let hay = "abc";
+
+    let expect = expect![[r#"
+        [
+            0..1,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn leftmost_longest() {
+    let pat = "a|abc";
+    let hay = "abc abd";
+
+    let expect = expect![[r#"
+        [
+            0..3,
+            4..5,
+        ]
+    "#]];
+
+    let actual = RegexBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(pat)
+        .unwrap()
+        .matches(hay.bytes())
+        .collect::<Vec<_>>();
+
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn match_continues_past_non_matching_state() {
+    let pat = "a(bc)?d?";
+    let hay = "abcd abd";
+
+    let expect = expect![[r#"
+        [
+            0..4,
+            5..6,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn leftmost_match_wins_over_earlier_end() {
+    let pat = "abcd|bc";
+    let hay = "abcd abce";
+
+    let expect = expect![[r#"
+        [
+            0..4,
+            6..8,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn leftmost_start() {
+    let pat = "[a-z]+ing";
+    let hay = "singing sing";
+
+    let expect = expect![[r#"
+        [
+            0..7,
+            8..12,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn empty_and_non_empty_matches() {
+    let pat = "a*";
+    let hay = "baab";
+
+    let expect = expect![[r#"
+        [
+            0..0,
+            1..3,
+            4..4,
+        ]
+    "#]];
+
+    check(pat, hay, expect);
+}
+
+#[test]
+fn search_backwards_leftmost_longest() {
+    let pat = "c|abc";
+    let hay = "abc";
+
+    let expect = expect![[r#"
+        [
+            0..3,
+        ]
+    "#]];
+
+    let actual = RegexBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(pat)
+        .unwrap()
+        .rmatches(hay.bytes().rev())
+        .collect::<Vec<_>>();
+
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn search_backwards_exact() {
+    let pat = r"\bhey|a+";
+    let hay = "hey aa hey";
+
+    let expect = expect![[r#"
+        [
+            7..10,
+            4..6,
+            0..3,
+        ]
+    "#]];
+
+    let regex = Regex::new(pat).unwrap();
+    let actual = regex.rmatches_exact(hay.bytes()).collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+
+    let actual = regex
+        .rmatches_with_len(hay.bytes().rev(), hay.len())
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+#[should_panic = "the haystack is longer than its length"]
+fn search_backwards_too_long() {
+    let regex = Regex::new("hey").unwrap();
+    regex
+        .rmatches_with_len("hey abc".bytes().rev(), 4)
+        .for_each(drop);
+}
+
+#[test]
+fn long_haystack() {
+    let len = 1 << 16;
+    let hay = "x".repeat(len) + "needle" + &"x".repeat(len);
+
+    let regex = Regex::new("needle|x{1000}y").unwrap();
+    let mut matches = regex.matches(hay.bytes());
+
+    assert_eq!(matches.next(), Some(len..len + 6));
+    assert_eq!(matches.next(), None);
+}
+
+fn check_captures(pat: &str, hay: &str, expect: Expect) {
+    let actual = Regex::new(pat)
+        .unwrap()
+        .captures(hay.bytes())
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn captures() {
+    let pat = r"(\w+)=(\d+)?";
+    let hay = "a=1 bc= d=23";
+
+    let expect = expect![[r#"
+        [
+            [
+                Some(
+                    0..3,
+                ),
+                Some(
+                    0..1,
+                ),
+                Some(
+                    2..3,
+                ),
+            ],
+            [
+                Some(
+                    4..7,
+                ),
+                Some(
+                    4..6,
+                ),
+                None,
+            ],
+            [
+                Some(
+                    8..12,
+                ),
+                Some(
+                    8..9,
+                ),
+                Some(
+                    10..12,
+                ),
+            ],
+        ]
+    "#]];
+
+    check_captures(pat, hay, expect);
+}
+
+#[test]
+fn captures_alternation() {
+    let pat = "(a)|(ab)(c)?";
+    let hay = "abc ab a";
+
+    let expect = expect![[r#"
+        [
+            [
+                Some(
+                    0..1,
+                ),
+                Some(
+                    0..1,
+                ),
+                None,
+                None,
+            ],
+            [
+                Some(
+                    4..5,
+                ),
+                Some(
+                    4..5,
+                ),
+                None,
+                None,
+            ],
+            [
+                Some(
+                    7..8,
+                ),
+                Some(
+                    7..8,
+                ),
+                None,
+                None,
+            ],
+        ]
+    "#]];
+
+    check_captures(pat, hay, expect);
+}
+
+#[test]
+fn captures_leftmost_longest() {
+    let pat = "(a)|(ab)(c)?";
+    let hay = "abc ab a";
+
+    let expect = expect![[r#"
+        [
+            [
+                Some(
+                    0..3,
+                ),
+                None,
+                Some(
+                    0..2,
+                ),
+                Some(
+                    2..3,
+                ),
+            ],
+            [
+                Some(
+                    4..6,
+                ),
+                None,
+                Some(
+                    4..6,
+                ),
+                None,
+            ],
+            [
+                Some(
+                    7..8,
+                ),
+                Some(
+                    7..8,
+                ),
+                None,
+                None,
+            ],
+        ]
+    "#]];
+
+    let regex = RegexBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(pat)
+        .unwrap();
+    let actual = regex.captures(hay.bytes()).collect::<Vec<_>>();
+
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn captures_repeated_group() {
+    let pat = "(?:(a)|(b))+";
+    let hay = "abba";
+
+    let expect = expect![[r#"
+        [
+            [
+                Some(
+                    0..4,
+                ),
+                Some(
+                    3..4,
+                ),
+                Some(
+                    2..3,
+                ),
+            ],
+        ]
+    "#]];
+
+    check_captures(pat, hay, expect);
+}
+
+#[test]
+fn named_captures() {
+    let regex = Regex::new(r"(?P<key>\w+)=(?P<value>\w*)").unwrap();
+    let caps = regex.captures(" key=value".bytes()).next().unwrap();
+
+    assert_eq!(
+        regex.capture_names().collect::<Vec<_>>(),
+        [None, Some("key"), Some("value")]
+    );
+    assert_eq!(caps.name("key"), Some(1..4));
+    assert_eq!(caps.name("value"), Some(5..10));
+    assert_eq!(caps.name("nope"), None);
+}
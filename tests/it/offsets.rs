@@ -0,0 +1,58 @@
+use std::io::Cursor;
+
+use hotsauce::Regex;
+
+const BASE: u64 = 5 << 32;
+
+#[test]
+fn matches_from() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let actual = regex
+        .matches_from("ab 123 c 45".bytes(), BASE)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, [BASE + 3..BASE + 6, BASE + 9..BASE + 11]);
+}
+
+#[test]
+fn look_around_from() {
+    let regex = Regex::new(r"^\w+|\bc$").unwrap();
+    let actual = regex
+        .matches_from("ab 123 c".bytes(), BASE)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, [BASE..BASE + 2, BASE + 7..BASE + 8]);
+}
+
+#[test]
+fn searcher_from() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let mut searcher = regex.searcher_from(BASE);
+
+    assert_eq!(searcher.feed(b"ab 12").next(), None);
+    assert_eq!(searcher.feed(b"3 c 4").next(), Some(BASE + 3..BASE + 6));
+    assert_eq!(searcher.finish().next(), Some(BASE + 9..BASE + 10));
+}
+
+#[test]
+fn matches_reader_from() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let actual = regex
+        .matches_reader_from(Cursor::new("ab 123 c 45"), BASE)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(actual, [BASE + 3..BASE + 6, BASE + 9..BASE + 11]);
+}
+
+#[test]
+fn matches_from_near_end() {
+    let base = u64::MAX - 11;
+    let regex = Regex::new("[0-9]+").unwrap();
+    let actual = regex
+        .matches_from("ab 123 c 45".bytes(), base)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, [base + 3..base + 6, base + 9..base + 11]);
+
+    let regex = Regex::new(r"\b[0-9]+$").unwrap();
+    let mut actual = regex.matches_from("ab 123 c 45".bytes(), base);
+    assert_eq!(actual.next(), Some(base + 9..base + 11));
+    assert_eq!(actual.next(), None);
+}
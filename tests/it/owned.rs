@@ -0,0 +1,166 @@
+use expect_test::expect;
+use hotsauce::{DfaKind, MatchKind, MatchTooLong, OwnedMatch, Regex, RegexBuilder};
+
+#[test]
+fn bytes_of_matches() {
+    let hay = "foo1 bar22 \u{1f336}baz x".repeat(3) + &"q".repeat(1000);
+    for pattern in [r"\w+", r"[a-z]+\b", r"\bba", "x*", "q+", r"\d+$|^\w"] {
+        let regex = Regex::new(pattern).unwrap();
+        let expected = regex::bytes::Regex::new(pattern)
+            .unwrap()
+            .find_iter(hay.as_bytes())
+            .map(|mat| OwnedMatch {
+                range: mat.range(),
+                bytes: mat.as_bytes().to_vec(),
+            })
+            .collect::<Vec<_>>();
+
+        let actual = regex
+            .matches(hay.bytes())
+            .with_bytes(usize::MAX)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected, "{}", pattern);
+    }
+}
+
+#[test]
+fn owned_matches() {
+    let regex = Regex::new(r"\d+|\bq").unwrap();
+    let hay = "a12 q345 bq";
+
+    let actual = regex.matches(hay.bytes()).with_bytes(2).collect::<Vec<_>>();
+
+    let expect = expect![[r#"
+        [
+            Ok(
+                OwnedMatch {
+                    range: 1..3,
+                    bytes: [
+                        49,
+                        50,
+                    ],
+                },
+            ),
+            Ok(
+                OwnedMatch {
+                    range: 4..5,
+                    bytes: [
+                        113,
+                    ],
+                },
+            ),
+            Err(
+                MatchTooLong {
+                    range: 5..8,
+                    max_len: 2,
+                },
+            ),
+        ]
+    "#]];
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn too_long() {
+    let regex = Regex::new("[a-z]+").unwrap();
+    let hay = "abc defgh ij";
+
+    let actual = regex.matches(hay.bytes()).with_bytes(3).collect::<Vec<_>>();
+    assert_eq!(
+        actual,
+        [
+            Ok(OwnedMatch {
+                range: 0..3,
+                bytes: b"abc".to_vec(),
+            }),
+            Err(MatchTooLong {
+                range: 4..9,
+                max_len: 3,
+            }),
+            Ok(OwnedMatch {
+                range: 10..12,
+                bytes: b"ij".to_vec(),
+            }),
+        ]
+    );
+
+    let err = actual[1].clone().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "match at 4..9 exceeds maximum length of 3 bytes"
+    );
+}
+
+#[test]
+fn truncate() {
+    let regex = Regex::new("[a-z]+").unwrap();
+    let hay = "abc defgh";
+
+    let actual = regex
+        .matches(hay.bytes())
+        .with_bytes(3)
+        .truncate(true)
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actual,
+        [
+            OwnedMatch {
+                range: 0..3,
+                bytes: b"abc".to_vec(),
+            },
+            OwnedMatch {
+                range: 4..9,
+                bytes: b"def".to_vec(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn long_matches() {
+    // Most bytes of these matches are dropped while the search goes on.
+    let hay = "ab".repeat(5000) + " x " + &"a".repeat(3000) + "b" + &"c".repeat(5000) + "z";
+    let patterns = [r"[a-z]+", r"\b[ab]+\b", r"x [a-z]*y|x", r"(ab)+ x|b"];
+    let mut longest = RegexBuilder::new();
+    longest.match_kind(MatchKind::LeftmostLongest);
+    let mut lazy = RegexBuilder::new();
+    lazy.dfa_kind(DfaKind::Lazy);
+
+    for builder in [RegexBuilder::new(), longest, lazy] {
+        for pattern in patterns {
+            let regex = builder.build(pattern).unwrap();
+            let expected = regex
+                .matches(hay.bytes())
+                .map(|range| OwnedMatch {
+                    bytes: hay.as_bytes()[range.clone()]
+                        .iter()
+                        .take(10)
+                        .copied()
+                        .collect(),
+                    range,
+                })
+                .collect::<Vec<_>>();
+
+            let actual = regex
+                .matches(hay.bytes())
+                .with_bytes(10)
+                .truncate(true)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected, "{}", pattern);
+
+            let actual = regex
+                .matches(hay.bytes())
+                .with_bytes(10)
+                .map(|mat| mat.map_or_else(|err| err.range, |mat| mat.range))
+                .collect::<Vec<_>>();
+            let expected = expected
+                .into_iter()
+                .map(|mat| mat.range)
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected, "{}", pattern);
+        }
+    }
+}
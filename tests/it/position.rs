@@ -0,0 +1,152 @@
+use expect_test::expect;
+use hotsauce::{DfaKind, LineColumn, Match, Regex, RegexBuilder};
+
+/// Computes the positions of the matches the `regex` crate finds from the whole haystack.
+fn naive(pattern: &str, hay: &str, crlf: bool, tab_width: usize) -> Vec<Match> {
+    let position = |end: usize| {
+        let mut pos = LineColumn { line: 1, column: 1 };
+        let mut prev = None;
+        for c in hay[..end].chars() {
+            match c {
+                '\n' if !crlf || prev == Some('\r') => {
+                    pos = LineColumn {
+                        line: pos.line + 1,
+                        column: 1,
+                    }
+                }
+                '\t' => pos.column = (pos.column - 1) / tab_width * tab_width + tab_width + 1,
+                _ => pos.column += 1,
+            }
+            prev = Some(c);
+        }
+        pos
+    };
+    let chars = |end: usize| hay[..end].chars().count();
+
+    regex::Regex::new(pattern)
+        .unwrap()
+        .find_iter(hay)
+        .map(|mat| Match {
+            chars: chars(mat.start())..chars(mat.end()),
+            start: position(mat.start()),
+            end: position(mat.end()),
+            bytes: mat.range(),
+        })
+        .collect()
+}
+
+fn check(builder: &RegexBuilder, pattern: &str, hay: &str) {
+    let regex = builder.build(pattern).unwrap();
+    for crlf in [false, true] {
+        for tab_width in [1, 4, 8] {
+            let actual = regex
+                .matches(hay.bytes())
+                .positions()
+                .crlf(crlf)
+                .tab_width(tab_width)
+                .collect::<Vec<_>>();
+            assert_eq!(actual, naive(pattern, hay, crlf, tab_width));
+        }
+    }
+}
+
+const HAY: &str =
+    "fn main() {\r\n\tlet wörld = \"héllo\";\n\t\tprintln!(\"{}\", wörld);\r\n}\n\n\tx\ty\n";
+
+#[test]
+fn dense() {
+    check(&RegexBuilder::new(), r"[a-zö]+", HAY);
+    check(&RegexBuilder::new(), r"\t+", HAY);
+}
+
+#[test]
+fn lazy() {
+    let mut lazy = RegexBuilder::new();
+    lazy.dfa_kind(DfaKind::Lazy);
+    check(&lazy, r"\w+\(", HAY);
+}
+
+#[test]
+fn look_around() {
+    check(&RegexBuilder::new(), r"(?m)^\s*\w|\bwörld\b", HAY);
+}
+
+#[test]
+fn empty_matches() {
+    // Empty matches between the bytes of a character can't be sliced, so stick to ASCII.
+    check(&RegexBuilder::new(), "x*", "\tx\r\nxx\n\n\t\tx");
+    check(&RegexBuilder::new(), "x*", "");
+}
+
+#[test]
+fn crlf() {
+    let regex = Regex::new("b").unwrap();
+    let hay = "a\r\nb\nb";
+
+    let ends = |crlf| {
+        regex
+            .matches(hay.bytes())
+            .positions()
+            .crlf(crlf)
+            .map(|mat| mat.end)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        ends(false),
+        [
+            LineColumn { line: 2, column: 2 },
+            LineColumn { line: 3, column: 2 },
+        ]
+    );
+    assert_eq!(
+        ends(true),
+        [
+            LineColumn { line: 2, column: 2 },
+            LineColumn { line: 2, column: 4 },
+        ]
+    );
+}
+
+#[test]
+fn positions() {
+    let regex = Regex::new("wö+").unwrap();
+    let hay = "a\twörld\r\nwöö";
+
+    let actual = regex
+        .matches(hay.bytes())
+        .positions()
+        .tab_width(4)
+        .crlf(true)
+        .collect::<Vec<_>>();
+
+    let expect = expect![[r#"
+        [
+            Match {
+                bytes: 2..5,
+                chars: 2..4,
+                start: LineColumn {
+                    line: 1,
+                    column: 5,
+                },
+                end: LineColumn {
+                    line: 1,
+                    column: 7,
+                },
+            },
+            Match {
+                bytes: 10..15,
+                chars: 9..12,
+                start: LineColumn {
+                    line: 2,
+                    column: 1,
+                },
+                end: LineColumn {
+                    line: 2,
+                    column: 4,
+                },
+            },
+        ]
+    "#]];
+    expect.assert_debug_eq(&actual);
+}
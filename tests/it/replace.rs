@@ -0,0 +1,106 @@
+use expect_test::{expect, Expect};
+use hotsauce::{NoExpand, Regex, RegexBuilder, Replacer};
+
+fn check(pat: &str, hay: &str, replacer: impl Replacer, expect: Expect) {
+    let actual = Regex::new(pat)
+        .unwrap()
+        .replace_all(hay.bytes(), replacer)
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&String::from_utf8(actual).unwrap());
+}
+
+#[test]
+fn no_match() {
+    let pat = "foo";
+    let hay = "bar baz";
+
+    let expect = expect![[r#"
+        "bar baz"
+    "#]];
+
+    check(pat, hay, "qux", expect);
+}
+
+#[test]
+fn literal() {
+    let pat = "a+";
+    let hay = "baaad caab";
+
+    let expect = expect![[r#"
+        "b-d c-b"
+    "#]];
+
+    check(pat, hay, "-", expect);
+}
+
+#[test]
+fn template() {
+    let pat = r"(\w+)@(?P<host>\w+)";
+    let hay = "mail alice@example or bob@test";
+
+    let expect = expect![[r#"
+        "mail [$1 at example: alice] or [$1 at test: bob]"
+    "#]];
+
+    check(pat, hay, "[$$1 at ${host}: $1]", expect);
+}
+
+#[test]
+fn template_missing_groups() {
+    let pat = r"a(b)?";
+    let hay = "ab a";
+
+    let expect = expect![[r#"
+        "<b|$> <|$>"
+    "#]];
+
+    check(pat, hay, "<$1|$2$nope$>", expect);
+}
+
+#[test]
+fn no_expand() {
+    let pat = r"\d+";
+    let hay = "1 + 22";
+
+    let expect = expect![[r#"
+        "$0 + $0"
+    "#]];
+
+    check(pat, hay, NoExpand("$0"), expect);
+}
+
+#[test]
+fn closure() {
+    let pat = r"[a-z]+";
+    let hay = "foo, bar!";
+
+    let expect = expect![[r#"
+        "FOO, BAR!"
+    "#]];
+
+    check(pat, hay, |bytes: &[u8]| bytes.to_ascii_uppercase(), expect);
+}
+
+#[test]
+fn empty_matches() {
+    let pat = "x*";
+    let hay = "abxc";
+
+    let expect = expect![[r#"
+        "-a-b-c-"
+    "#]];
+
+    check(pat, hay, "-", expect);
+}
+
+#[test]
+fn leftmost_longest() {
+    let regex = RegexBuilder::new()
+        .match_kind(hotsauce::MatchKind::LeftmostLongest)
+        .build("a|ab")
+        .unwrap();
+    let actual = regex
+        .replace_all("abc ab".bytes(), "<$0>")
+        .collect::<Vec<_>>();
+    assert_eq!(b"<ab>c <ab>".to_vec(), actual);
+}
@@ -0,0 +1,133 @@
+use hotsauce::{DfaKind, MatchKind, Regex, RegexBuilder};
+
+/// Feeds the haystack in chunks of every size, checking the searcher agrees with `matches`.
+fn check(regex: &Regex, hay: &str) {
+    let expected = regex
+        .matches(hay.bytes())
+        .map(|mat| mat.start as u64..mat.end as u64)
+        .collect::<Vec<_>>();
+
+    for size in 1..=hay.len().max(1) {
+        let mut searcher = regex.searcher();
+        let mut actual = vec![];
+        for chunk in hay.as_bytes().chunks(size) {
+            actual.extend(searcher.feed(chunk));
+        }
+        actual.extend(searcher.finish());
+        assert_eq!(actual, expected, "chunks of {} bytes", size);
+    }
+}
+
+#[test]
+fn dense() {
+    let regex = Regex::new("[0-9]+|ab+c").unwrap();
+    check(&regex, "x 123 abbbc 4 abbd 56");
+}
+
+#[test]
+fn leftmost_longest() {
+    let regex = RegexBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build("a|abc|abcdef")
+        .unwrap();
+    check(&regex, "abcde abcdef ab");
+}
+
+#[test]
+fn empty_matches() {
+    let regex = Regex::new("x*").unwrap();
+    check(&regex, "axxbx");
+    check(&regex, "");
+}
+
+#[test]
+fn look_around() {
+    let regex = Regex::new(r"\bfoo\b|bar$").unwrap();
+    check(&regex, "foo food foo bar barbar");
+}
+
+#[test]
+fn split_character() {
+    // The character after the boundary is only known once all of its bytes were fed.
+    let regex = Regex::new(r"x\b").unwrap();
+    let mut searcher = regex.searcher();
+    assert_eq!(searcher.feed(b"x\xc3").next(), None);
+    assert_eq!(searcher.feed(b"\xa9").next(), None);
+    assert_eq!(searcher.finish().next(), None);
+
+    check(&regex, "xé x🌶 x");
+    check(&Regex::new(r"\B🌶|é\b").unwrap(), "a🌶é 🌶🌶é");
+}
+
+#[test]
+fn lazy() {
+    let regex = RegexBuilder::new()
+        .dfa_kind(DfaKind::Lazy)
+        .build("[01]*1[01]{5}")
+        .unwrap();
+    check(&regex, "0010000 11111100 2100000");
+}
+
+#[test]
+fn thrashing_cache() {
+    // Checking whether the search can end early mustn't clear the cache under the state checked.
+    for pattern in ["a*b", "[a-c]+?", "(a|ab)(c|bcd)(d*)"] {
+        let regex = RegexBuilder::new()
+            .dfa_kind(DfaKind::Lazy)
+            .lazy_cache_size(0)
+            .build(pattern)
+            .unwrap();
+        check(&regex, "b ac abcd aab");
+    }
+}
+
+#[test]
+fn pike_vm() {
+    let regex = RegexBuilder::new()
+        .dfa_kind(DfaKind::Lazy)
+        .lazy_cache_size(0)
+        .build(r"\bab+c|x+$")
+        .unwrap();
+    check(&regex, "abbc xabc abbbbc xx");
+}
+
+#[test]
+fn single_byte_feeds() {
+    // Each byte only continues the search, instead of searching the match so far again.
+    let len = 40_000;
+    let hay = "a".to_owned() + &"b".repeat(len) + "z";
+
+    for kind in [DfaKind::Dense, DfaKind::Lazy] {
+        let regex = RegexBuilder::new().dfa_kind(kind).build("a[^z]*z").unwrap();
+        let mut searcher = regex.searcher();
+        let mut actual = vec![];
+        for b in hay.bytes() {
+            actual.extend(searcher.feed(&[b]));
+        }
+        actual.extend(searcher.finish());
+        assert_eq!(actual, vec![0..len as u64 + 2]);
+    }
+}
+
+#[test]
+fn matches_left_in_iterator() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let mut searcher = regex.searcher();
+
+    assert_eq!(searcher.feed(b"1 2 3").next(), Some(0..1));
+    assert_eq!(searcher.feed(b" 4").collect::<Vec<_>>(), [2..3, 4..5]);
+    let mut finished = searcher.finish();
+    assert_eq!(finished.next(), Some(6..7));
+    assert_eq!(finished.next(), None);
+}
+
+#[test]
+fn match_without_next_byte() {
+    // No byte can continue these matches, so they're found before the next one is fed.
+    let regex = Regex::new("[0-9]|ab").unwrap();
+    let mut searcher = regex.searcher();
+    assert_eq!(searcher.feed(b"x1").next(), Some(1..2));
+    assert_eq!(searcher.feed(b"ab").next(), Some(2..4));
+    assert_eq!(searcher.feed(b"a").next(), None);
+    assert_eq!(searcher.finish().next(), None);
+}
@@ -0,0 +1,210 @@
+use hotsauce::{DfaKind, MatchKind, Regex, RegexBuilder, StateIdWidth};
+
+fn roundtrip(regex: &Regex, hay: &str) {
+    let bytes = regex.to_bytes_native_endian();
+    let loaded = Regex::from_bytes(&bytes).unwrap();
+
+    let expected = regex.matches(hay.bytes()).collect::<Vec<_>>();
+    assert!(!expected.is_empty());
+    assert_eq!(loaded.matches(hay.bytes()).collect::<Vec<_>>(), expected);
+    assert_eq!(
+        loaded.rmatches(hay.bytes().rev()).collect::<Vec<_>>(),
+        regex.rmatches(hay.bytes().rev()).collect::<Vec<_>>(),
+    );
+    assert_eq!(loaded.to_bytes_native_endian(), bytes);
+}
+
+#[test]
+fn dense() {
+    let regex = Regex::new(r"[a-z]+\d*").unwrap();
+    roundtrip(&regex, "abc12 de 3 f");
+}
+
+#[test]
+fn lazy_and_look_around() {
+    let regex = RegexBuilder::new()
+        .dfa_kind(DfaKind::Lazy)
+        .build("[01]*1[01]{5}")
+        .unwrap();
+    roundtrip(&regex, "0010000 11111100");
+
+    let regex = Regex::new(r"\bfoo\b|^bar").unwrap();
+    roundtrip(&regex, "bar foo food foo");
+}
+
+#[test]
+fn match_kind_and_captures() {
+    let regex = RegexBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(r"(?P<key>a|ab)=(\w+)")
+        .unwrap();
+    let loaded = Regex::from_bytes(&regex.to_bytes_native_endian()).unwrap();
+
+    let caps = loaded.captures("x ab=cd".bytes()).next().unwrap();
+    assert_eq!(caps.get(0), Some(2..7));
+    assert_eq!(caps.name("key"), Some(2..4));
+    assert_eq!(caps.get(2), Some(5..7));
+    assert_eq!(
+        loaded.capture_names().collect::<Vec<_>>(),
+        [None, Some("key"), None]
+    );
+}
+
+#[test]
+fn mismatched_nfa() {
+    // The NFA resolving capture groups comes last, prefixed with its length.
+    let nfa_start = |bytes: &[u8]| {
+        (0..bytes.len() - 8)
+            .find(|&i| {
+                let len = u64::from_ne_bytes(bytes[i..i + 8].try_into().unwrap());
+                len == (bytes.len() - i - 8) as u64
+            })
+            .unwrap()
+    };
+    let bytes = Regex::new("(a)(b)").unwrap().to_bytes_native_endian();
+    let other = Regex::new("(x)(y)").unwrap().to_bytes_native_endian();
+    let spliced = [&bytes[..nfa_start(&bytes)], &other[nfa_start(&other)..]].concat();
+
+    let loaded = Regex::from_bytes(&spliced).unwrap();
+    let caps = loaded.captures("ab xy".bytes()).collect::<Vec<_>>();
+    assert_eq!(format!("{caps:?}"), "[[Some(0..2), None, None]]");
+}
+
+#[test]
+fn zero_copy() {
+    let regex = Regex::new(r"[a-z]+\d*").unwrap();
+    let bytes = regex.to_bytes_native_endian();
+
+    // Copy the bytes to a buffer aligned to 8 bytes.
+    let words: &'static mut [u64] = vec![0; bytes.len() / 8 + 1].leak();
+    let aligned: &'static mut [u8] =
+        unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr().cast(), words.len() * 8) };
+    aligned[..bytes.len()].copy_from_slice(&bytes);
+
+    let loaded = unsafe { Regex::from_bytes_unchecked(&aligned[..bytes.len()]) }.unwrap();
+    assert_eq!(
+        loaded.matches("abc12 de 3 f".bytes()).collect::<Vec<_>>(),
+        [0..5, 6..8, 11..12]
+    );
+    assert_eq!(loaded.to_bytes_native_endian(), bytes);
+    // The DFAs are not on the heap.
+    assert!(loaded.memory_usage() < regex.memory_usage());
+
+    let err = unsafe { Regex::from_bytes_unchecked(&aligned[1..=bytes.len()]) }.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to deserialize regex: bytes are not aligned to 8 bytes at offset 0"
+    );
+}
+
+#[test]
+fn invalid_bytes() {
+    let regex = Regex::new(r"[a-z]+\d*").unwrap();
+    let native = regex.to_bytes_native_endian();
+    let other = if cfg!(target_endian = "big") {
+        regex.to_bytes_little_endian()
+    } else {
+        regex.to_bytes_big_endian()
+    };
+
+    let message = |bytes: &[u8]| Regex::from_bytes(bytes).unwrap_err().to_string();
+
+    assert_eq!(
+        message(&other),
+        "failed to deserialize regex: serialized with a different byte order at offset 18"
+    );
+    assert_eq!(
+        message(b"not a regex at all"),
+        "failed to deserialize regex: not a serialized regex at offset 16"
+    );
+    assert!(message(&native[..native.len() - 1]).contains("unexpected end of bytes"));
+
+    let mut trailing = native.clone();
+    trailing.push(0);
+    assert!(message(&trailing).contains("trailing bytes"));
+
+    // Point the last transition of the first DFA out of bounds.
+    let mut corrupted = native.clone();
+    let len = usize::try_from(u64::from_ne_bytes(native[24..32].try_into().unwrap())).unwrap();
+    corrupted[32 + len - 8..32 + len].fill(0xFF);
+    assert!(message(&corrupted).contains("transition out of bounds"));
+}
+
+#[test]
+fn corrupted_automata() {
+    let message = |bytes: &[u8]| Regex::from_bytes(bytes).unwrap_err().to_string();
+
+    // Both directions are searched with the Pike VM, each NFA comes after its tag and length.
+    let native = Regex::new(r"\ba").unwrap().to_bytes_native_endian();
+    let len = usize::try_from(u64::from_ne_bytes(native[24..32].try_into().unwrap())).unwrap();
+    assert_eq!((native[23], native[32 + len]), (2, 2));
+
+    // Tag them as lazy DFAs, which can't search look-around.
+    let lazy = [&[1][..], &(1u64 << 20).to_ne_bytes()].concat();
+    let retagged = [
+        &native[..23],
+        &lazy,
+        &native[24..32 + len],
+        &lazy,
+        &native[33 + len..],
+    ]
+    .concat();
+    assert!(message(&retagged).contains("lazy DFA with look-around"));
+
+    // Start the forward NFA inside of group 0, so its matches have no start.
+    let mut corrupted = native.clone();
+    let start = u64::from_ne_bytes(native[32..40].try_into().unwrap());
+    corrupted[32..40].copy_from_slice(&(start + 2).to_ne_bytes());
+    assert!(message(&corrupted).contains("unexpected NFA"));
+}
+
+#[test]
+fn compact() {
+    for sparse in [false, true] {
+        for width in [StateIdWidth::U8, StateIdWidth::U16, StateIdWidth::Usize] {
+            if !sparse && width == StateIdWidth::Usize {
+                continue;
+            }
+            let regex = RegexBuilder::new()
+                .premultiply(false)
+                .sparse(sparse)
+                .state_id_width(width)
+                .build("[a-z]+[0-9]*")
+                .unwrap();
+            roundtrip(&regex, "abc12 de 3 f");
+
+            let bytes = regex.to_bytes_native_endian();
+            let words: &'static mut [u64] = vec![0; bytes.len() / 8 + 1].leak();
+            let aligned: &'static mut [u8] = unsafe {
+                std::slice::from_raw_parts_mut(words.as_mut_ptr().cast(), words.len() * 8)
+            };
+            aligned[..bytes.len()].copy_from_slice(&bytes);
+            let loaded = unsafe { Regex::from_bytes_unchecked(&aligned[..bytes.len()]) }.unwrap();
+            assert_eq!(
+                loaded.matches("abc12 de 3 f".bytes()).collect::<Vec<_>>(),
+                [0..5, 6..8, 11..12]
+            );
+        }
+    }
+}
+
+#[test]
+fn invalid_sparse_bytes() {
+    let regex = RegexBuilder::new()
+        .sparse(true)
+        .state_id_width(StateIdWidth::U16)
+        .build("[a-z]+[0-9]*")
+        .unwrap();
+    let native = regex.to_bytes_native_endian();
+
+    // Point the last transition of the first DFA out of bounds.
+    let mut corrupted = native.clone();
+    let len = usize::try_from(u64::from_ne_bytes(native[25..33].try_into().unwrap())).unwrap();
+    corrupted[40 + len - 2..40 + len].fill(0xFF);
+    let err = Regex::from_bytes(&corrupted).unwrap_err();
+    assert!(
+        err.to_string().contains("transition out of bounds"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,268 @@
+use expect_test::{expect, Expect};
+use hotsauce::{MatchKind, RegexBuilder, RegexSet};
+
+fn check(pats: &[&str], hay: &str, expect: Expect) {
+    let actual = RegexSet::new(pats)
+        .unwrap()
+        .matches(hay.bytes())
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn no_match() {
+    let pats = ["foo", "bar"];
+    let hay = "baz";
+
+    let expect = expect![[r#"
+        []
+    "#]];
+
+    check(&pats, hay, expect);
+}
+
+#[test]
+fn multiple_patterns() {
+    let pats = ["foo", "ba[rz]", r"\d+"];
+    let hay = "foo 12 baz foo";
+
+    let expect = expect![[r#"
+        [
+            (
+                0,
+                0..3,
+            ),
+            (
+                2,
+                4..6,
+            ),
+            (
+                1,
+                7..10,
+            ),
+            (
+                0,
+                11..14,
+            ),
+        ]
+    "#]];
+
+    check(&pats, hay, expect);
+}
+
+#[test]
+fn earlier_pattern_preferred() {
+    let pats = ["a", "abc", "b"];
+    let hay = "abc";
+
+    let expect = expect![[r#"
+        [
+            (
+                0,
+                0..1,
+            ),
+            (
+                2,
+                1..2,
+            ),
+        ]
+    "#]];
+
+    check(&pats, hay, expect);
+}
+
+#[test]
+fn leftmost_pattern_wins() {
+    let pats = ["bc", "abcd"];
+    let hay = "abcd abce";
+
+    let expect = expect![[r#"
+        [
+            (
+                1,
+                0..4,
+            ),
+            (
+                0,
+                6..8,
+            ),
+        ]
+    "#]];
+
+    check(&pats, hay, expect);
+}
+
+#[test]
+fn leftmost_longest() {
+    let pats = ["a", "abc", "b"];
+    let hay = "abc ab";
+
+    let expect = expect![[r#"
+        [
+            (
+                1,
+                0..3,
+            ),
+            (
+                0,
+                4..5,
+            ),
+            (
+                2,
+                5..6,
+            ),
+        ]
+    "#]];
+
+    let actual = RegexBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build_set(pats)
+        .unwrap()
+        .matches(hay.bytes())
+        .collect::<Vec<_>>();
+
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn empty_matches() {
+    let pats = ["x", ""];
+    let hay = "axb";
+
+    let expect = expect![[r#"
+        [
+            (
+                1,
+                0..0,
+            ),
+            (
+                0,
+                1..2,
+            ),
+            (
+                1,
+                3..3,
+            ),
+        ]
+    "#]];
+
+    check(&pats, hay, expect);
+}
+
+#[test]
+fn which_match() {
+    let set = RegexSet::new(["foo", "o+", "bar", r"\d"]).unwrap();
+
+    assert_eq!(set.which_match("foo bar".bytes()), [0, 1, 2]);
+    assert_eq!(set.which_match("".bytes()), [] as [usize; 0]);
+    assert!(set.matches_any("42".bytes()));
+    assert!(!set.matches_any("xyz".bytes()));
+}
+
+#[test]
+fn matches_any_stops_early() {
+    let set = RegexSet::new(["foo"]).unwrap();
+    let mut hay = "foo bar".bytes();
+
+    assert!(set.matches_any(&mut hay));
+    assert_eq!(hay.collect::<Vec<_>>(), b" bar");
+}
+
+#[test]
+fn matches_any_with_lazy_dfa() {
+    let hay = "fo ".repeat(1000) + "foo7 bar";
+
+    // Without a cache, the lazy DFA gives up and the Pike VM takes over.
+    for cache_size in [1 << 20, 0] {
+        let mut builder = RegexBuilder::new();
+        builder.lazy_cache_size(cache_size);
+
+        let set = builder.build_set(["bar", "fo+[0-9]"]).unwrap();
+        let mut bytes = hay.bytes();
+        assert!(set.matches_any(&mut bytes));
+        assert_eq!(bytes.collect::<Vec<_>>(), b" bar");
+
+        let set = builder.build_set(["bar[0-9]", "fo+[A-Z]"]).unwrap();
+        assert!(!set.matches_any(hay.bytes()));
+
+        let set = builder.build_set([r"\bbar\b", r"\bfo+[0-9]"]).unwrap();
+        assert!(set.matches_any(hay.bytes()));
+    }
+}
+
+#[test]
+fn look_around() {
+    let pats = [r"^\w+", r"\bbar\b", "baz$"];
+    let hay = "foo bar barbaz";
+
+    let expect = expect![[r#"
+        [
+            (
+                0,
+                0..3,
+            ),
+            (
+                1,
+                4..7,
+            ),
+            (
+                2,
+                11..14,
+            ),
+        ]
+    "#]];
+
+    check(&pats, hay, expect);
+}
+
+#[test]
+fn matches_like_alternation() {
+    let pats = [
+        "foo",
+        "fo+bar",
+        r"\d+",
+        "[a-z]+ing",
+        "bar",
+        r"\w+z",
+        "ab|abc",
+        "q*",
+    ];
+    let hay = "foo fooobar 123 sing bar4 buzz fizzbuzz ab abc x9z ".repeat(20);
+
+    // The regex crate finds the same matches, with the group telling which regex matched.
+    let alternation = pats.map(|pat| format!("({pat})")).join("|");
+    let expected = regex::Regex::new(&alternation)
+        .unwrap()
+        .captures_iter(&hay)
+        .map(|caps| {
+            let pattern = (1..caps.len()).find(|&i| caps.get(i).is_some()).unwrap() - 1;
+            (pattern, caps.get(0).unwrap().range())
+        })
+        .collect::<Vec<_>>();
+
+    // Without a cache, the lazy DFA gives up and the Pike VM takes over.
+    for cache_size in [1 << 20, 0] {
+        let set = RegexBuilder::new()
+            .lazy_cache_size(cache_size)
+            .build_set(pats)
+            .unwrap();
+        assert_eq!(set.matches(hay.bytes()).collect::<Vec<_>>(), expected);
+    }
+
+    let mut longest = RegexBuilder::new();
+    longest.match_kind(MatchKind::LeftmostLongest);
+    let expected = longest
+        .clone()
+        .lazy_cache_size(0)
+        .build_set(pats)
+        .unwrap()
+        .matches(hay.bytes())
+        .collect::<Vec<_>>();
+    let actual = longest
+        .build_set(pats)
+        .unwrap()
+        .matches(hay.bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+    assert!(actual.contains(&(6, 43..46)));
+}
@@ -0,0 +1,167 @@
+use expect_test::{expect, Expect};
+use hotsauce::Regex;
+
+fn check_split(pat: &str, hay: &str, expect: Expect) {
+    let actual = Regex::new(pat)
+        .unwrap()
+        .split(hay.bytes())
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+}
+
+fn check_segments(pat: &str, hay: &str, expect: Expect) {
+    let actual = Regex::new(pat)
+        .unwrap()
+        .segments(hay.bytes())
+        .collect::<Vec<_>>();
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn split() {
+    let pat = r"\s*;\s*";
+    let hay = "a; b ;c;";
+
+    let expect = expect![[r#"
+        [
+            0..1,
+            3..4,
+            6..7,
+            8..8,
+        ]
+    "#]];
+
+    check_split(pat, hay, expect);
+}
+
+#[test]
+fn split_no_match() {
+    let pat = ",";
+    let hay = "abc";
+
+    let expect = expect![[r#"
+        [
+            0..3,
+        ]
+    "#]];
+
+    check_split(pat, hay, expect);
+}
+
+#[test]
+fn split_empty_haystack() {
+    let pat = ",";
+    let hay = "";
+
+    let expect = expect![[r#"
+        [
+            0..0,
+        ]
+    "#]];
+
+    check_split(pat, hay, expect);
+}
+
+#[test]
+fn split_empty_matches() {
+    let pat = "";
+    let hay = "abc";
+
+    let expect = expect![[r#"
+        [
+            0..0,
+            0..1,
+            1..2,
+            2..3,
+            3..3,
+        ]
+    "#]];
+
+    check_split(pat, hay, expect);
+}
+
+#[test]
+fn splitn() {
+    let regex = Regex::new(",").unwrap();
+    let hay = "a,b,c,d";
+
+    let parts = |limit| regex.splitn(hay.bytes(), limit).collect::<Vec<_>>();
+
+    assert_eq!(Vec::<std::ops::Range<usize>>::new(), parts(0));
+    assert_eq!(vec![0..7], parts(1));
+    assert_eq!(vec![0..1, 2..7], parts(2));
+    assert_eq!(vec![0..1, 2..3, 4..5, 6..7], parts(4));
+    assert_eq!(vec![0..1, 2..3, 4..5, 6..7], parts(10));
+}
+
+#[test]
+fn segments() {
+    let pat = r"\d+";
+    let hay = "12ab3c45";
+
+    let expect = expect![[r#"
+        [
+            Match(
+                0..2,
+            ),
+            Gap(
+                2..4,
+            ),
+            Match(
+                4..5,
+            ),
+            Gap(
+                5..6,
+            ),
+            Match(
+                6..8,
+            ),
+        ]
+    "#]];
+
+    check_segments(pat, hay, expect);
+}
+
+#[test]
+fn segments_no_match() {
+    let pat = r"\d+";
+    let hay = "abc";
+
+    let expect = expect![[r#"
+        [
+            Gap(
+                0..3,
+            ),
+        ]
+    "#]];
+
+    check_segments(pat, hay, expect);
+}
+
+#[test]
+fn segments_empty_matches() {
+    let pat = "x*";
+    let hay = "axb";
+
+    let expect = expect![[r#"
+        [
+            Match(
+                0..0,
+            ),
+            Gap(
+                0..1,
+            ),
+            Match(
+                1..2,
+            ),
+            Gap(
+                2..3,
+            ),
+            Match(
+                3..3,
+            ),
+        ]
+    "#]];
+
+    check_segments(pat, hay, expect);
+}
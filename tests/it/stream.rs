@@ -0,0 +1,174 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    executor::block_on,
+    io::{AsyncRead, BufReader, Cursor},
+    stream, Stream, StreamExt, TryStreamExt,
+};
+use hotsauce::Regex;
+
+/// A stream which is pending before every byte.
+struct Slow<I> {
+    bytes: I,
+    ready: bool,
+}
+
+impl<I: Iterator<Item = u8> + Unpin> Stream for Slow<I> {
+    type Item = u8;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u8>> {
+        if std::mem::take(&mut self.ready) {
+            Poll::Ready(self.bytes.next())
+        } else {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn matches_stream() {
+    let regex = Regex::new("[0-9]+|x*").unwrap();
+    let hay = "ab 123 xx 4";
+
+    let expected = regex
+        .matches(hay.bytes())
+        .map(|mat| mat.start as u64..mat.end as u64)
+        .collect::<Vec<_>>();
+
+    let actual = block_on(
+        regex
+            .matches_stream(stream::iter(hay.bytes()))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(actual, expected);
+
+    let slow = Slow {
+        bytes: hay.bytes(),
+        ready: false,
+    };
+    let actual = block_on(regex.matches_stream(slow).collect::<Vec<_>>());
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn split_character() {
+    // Every byte arrives on its own, so each character is split.
+    let regex = Regex::new(r"x\b|\Bé").unwrap();
+    let hay = "xé x éé";
+    let slow = Slow {
+        bytes: hay.bytes(),
+        ready: false,
+    };
+    let actual = block_on(regex.matches_stream(slow).collect::<Vec<_>>());
+    assert_eq!(actual, [1..3, 4..5, 8..10]);
+}
+
+#[test]
+fn long_stream() {
+    let len = 1000;
+    let hay = "x".repeat(len) + "needle" + &"x".repeat(len);
+
+    let regex = Regex::new("needle").unwrap();
+    let mut matches = regex.matches_stream(stream::iter(hay.bytes()));
+    assert_eq!(block_on(matches.next()), Some(len as u64..len as u64 + 6));
+    assert_eq!(block_on(matches.next()), None);
+}
+
+#[test]
+fn long_match_in_small_chunks() {
+    // Every chunk continues the search where the previous one stopped.
+    let len = 40_000;
+    let hay = "a".to_owned() + &"b".repeat(len) + "z";
+    let regex = Regex::new("a[^z]*z").unwrap();
+
+    let slow = Slow {
+        bytes: hay.bytes(),
+        ready: false,
+    };
+    let actual = block_on(regex.matches_stream(slow).collect::<Vec<_>>());
+    assert_eq!(actual, vec![0..len as u64 + 2]);
+
+    let reader = BufReader::with_capacity(1, Cursor::new(hay));
+    let actual = block_on(regex.matches_async_reader(reader).try_collect::<Vec<_>>()).unwrap();
+    assert_eq!(actual, vec![0..len as u64 + 2]);
+}
+
+#[test]
+fn matches_async_reader() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let reader = BufReader::with_capacity(2, Cursor::new("12 345 6789"));
+
+    let actual = block_on(regex.matches_async_reader(reader).try_collect::<Vec<_>>()).unwrap();
+    assert_eq!(actual, [0..2, 3..6, 7..11]);
+}
+
+struct Failing(Cursor<&'static [u8]>);
+
+impl AsyncRead for Failing {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match Pin::new(&mut self.0).poll_read(cx, buf) {
+            Poll::Ready(Ok(0)) => Poll::Ready(Err(io::Error::other("connection reset"))),
+            poll => poll,
+        }
+    }
+}
+
+/// Is interrupted before every read.
+struct Interrupted<R> {
+    reader: R,
+    ready: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Interrupted<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if std::mem::take(&mut self.ready) {
+            Pin::new(&mut self.reader).poll_read(cx, buf)
+        } else {
+            self.ready = true;
+            Poll::Ready(Err(io::ErrorKind::Interrupted.into()))
+        }
+    }
+}
+
+#[test]
+fn async_reader_interrupted() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let reader = Interrupted {
+        reader: Cursor::new("12 345 6789"),
+        ready: false,
+    };
+    let reader = BufReader::with_capacity(2, reader);
+
+    let actual = block_on(regex.matches_async_reader(reader).try_collect::<Vec<_>>()).unwrap();
+    assert_eq!(actual, [0..2, 3..6, 7..11]);
+}
+
+#[test]
+fn async_reader_error() {
+    let regex = Regex::new("[0-9]+").unwrap();
+    let reader = BufReader::with_capacity(4, Failing(Cursor::new(b"12 34 56")));
+    let mut matches = regex.matches_async_reader(reader);
+
+    block_on(async {
+        assert_eq!(matches.next().await.unwrap().unwrap(), 0..2);
+        assert_eq!(matches.next().await.unwrap().unwrap(), 3..5);
+        // The last match might continue, so it's dropped.
+        let err = matches.next().await.unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "connection reset");
+        assert!(matches.next().await.is_none());
+    });
+}
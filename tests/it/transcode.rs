@@ -0,0 +1,79 @@
+use expect_test::expect;
+use hotsauce::Regex;
+
+/// Converts the matches the `regex` crate finds to indices of characters and UTF-16 code units.
+fn check(pattern: &str, hay: &str) {
+    let regex = Regex::new(pattern).unwrap();
+    let matches = regex::Regex::new(pattern)
+        .unwrap()
+        .find_iter(hay)
+        .map(|mat| mat.range())
+        .collect::<Vec<_>>();
+
+    let chars = |i: usize| hay[..i].chars().count();
+    let expected = matches
+        .iter()
+        .map(|mat| chars(mat.start)..chars(mat.end))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        regex.matches_chars(hay.chars()).collect::<Vec<_>>(),
+        expected
+    );
+
+    let units = |i: usize| hay[..i].encode_utf16().count();
+    let expected = matches
+        .iter()
+        .map(|mat| units(mat.start)..units(mat.end))
+        .collect::<Vec<_>>();
+    let actual = regex.matches_utf16(hay.encode_utf16()).collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+}
+
+const HAY: &str = "héllo 🌶🌶 wörld, ∂x/∂y = 🦀!";
+
+#[test]
+fn transcode() {
+    check(r"\w+", HAY);
+    check(r"🌶+|∂.", HAY);
+    check(r"\b\w", HAY);
+}
+
+#[test]
+fn empty_matches() {
+    // Empty matches between the bytes of a character are skipped.
+    check("x*", HAY);
+    check("x*", "");
+}
+
+#[test]
+fn indices() {
+    let regex = Regex::new(r"🌶+|ö\w").unwrap();
+
+    let actual = (
+        regex.matches_chars(HAY.chars()).collect::<Vec<_>>(),
+        regex.matches_utf16(HAY.encode_utf16()).collect::<Vec<_>>(),
+    );
+
+    let expect = expect![[r#"
+        (
+            [
+                6..8,
+                10..12,
+            ],
+            [
+                6..10,
+                12..14,
+            ],
+        )
+    "#]];
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn unpaired_surrogate() {
+    let regex = Regex::new("\u{fffd}b").unwrap();
+    let hay = [u16::from(b'a'), 0xd800, u16::from(b'b')];
+    let mut matches = regex.matches_utf16(hay.into_iter());
+    assert_eq!(matches.next(), Some(1..3));
+    assert_eq!(matches.next(), None);
+}
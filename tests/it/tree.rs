@@ -0,0 +1,109 @@
+use expect_test::expect;
+use hotsauce::Regex;
+
+/// Checks searching the chunks forwards finds what the `regex` crate finds in them joined,
+/// and searching them backwards agrees with searching them joined.
+fn check(pattern: &str, chunks: &[&str]) {
+    let regex = Regex::new(pattern).unwrap();
+    let hay = chunks.concat();
+
+    let expected = regex::Regex::new(pattern)
+        .unwrap()
+        .find_iter(&hay)
+        .map(|mat| mat.range())
+        .collect::<Vec<_>>();
+    assert_eq!(regex.matches_tree(chunks).collect::<Vec<_>>(), expected);
+
+    let expected = regex.rmatches_exact(hay.bytes()).collect::<Vec<_>>();
+    assert_eq!(regex.rmatches_tree(chunks).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn chunks() {
+    let pattern = r"[0-9]+|\bab+c";
+    check(pattern, &["x 1", "23 a", "", "bbbc 4 ab", "bd 56"]);
+    check(pattern, &[]);
+    check(pattern, &["", ""]);
+}
+
+#[test]
+fn empty_matches() {
+    check("x*", &["ax", "xb", "x"]);
+}
+
+#[test]
+fn across_chunks() {
+    let regex = Regex::new(r"\bab+c|[0-9]+").unwrap();
+    let chunks = ["12", "3 a", "bb", "c abc4", "5"];
+
+    let actual = (
+        regex.matches_tree(&chunks[..]).collect::<Vec<_>>(),
+        regex.rmatches_tree(&chunks[..]).collect::<Vec<_>>(),
+    );
+
+    let expect = expect![[r#"
+        (
+            [
+                0..3,
+                4..8,
+                9..12,
+                12..14,
+            ],
+            [
+                12..14,
+                9..12,
+                4..8,
+                0..3,
+            ],
+        )
+    "#]];
+    expect.assert_debug_eq(&actual);
+}
+
+#[test]
+fn many_chunks() {
+    // Chunks are searched a slice at a time in both directions, across their boundaries.
+    let pattern = r"[0-9]+|\bab+c|x{300}y";
+    let hay = ("x".repeat(5000) + "123 abbbc ").repeat(3) + &"b".repeat(9000) + "c";
+    let mut chunks = vec![];
+    let mut rest = &hay[..];
+    for size in (1..).map(|i| i * 97 % 5000) {
+        if rest.len() <= size {
+            chunks.push(rest);
+            break;
+        }
+        let (chunk, tail) = rest.split_at(size);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    check(pattern, &chunks);
+}
+
+#[cfg(feature = "ropey")]
+#[test]
+fn rope() {
+    use ropey::Rope;
+
+    let regex = Regex::new(r"\bnéedle\b").unwrap();
+    let text = "hay ".repeat(1000) + "néedle " + &"hay ".repeat(1000) + "néedle";
+    let rope = Rope::from_str(&text);
+    assert!(rope.chunks().count() > 1);
+
+    let start = text.find("néedle").unwrap();
+    let last = text.rfind("néedle").unwrap();
+    assert_eq!(
+        regex.matches_tree(&rope).collect::<Vec<_>>(),
+        [start..start + 7, last..last + 7]
+    );
+    assert_eq!(
+        regex.rmatches_tree(&rope).collect::<Vec<_>>(),
+        [last..last + 7, start..start + 7]
+    );
+
+    // Offsets are relative to the slice.
+    let slice = rope.slice(1..);
+    assert_eq!(
+        regex.matches_tree(&slice).next(),
+        Some(start - 1..start + 6)
+    );
+}
@@ -0,0 +1,45 @@
+//! How much memory searching takes, counted by the global allocator,
+//! in a test binary of its own so no other test allocates at the same time.
+#![cfg(feature = "std")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use hotsauce::Regex;
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn unterminated_match() {
+    // Every byte after the `a` might be part of a match, until a `z` that never comes.
+    let regex = Regex::new("a[^z]*z").unwrap();
+    let hay = "a".bytes().chain((0..2 << 20).map(|_| b'b'));
+
+    PEAK.store(ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let mat = regex.matches(hay).window_limit(Some(1 << 16)).next();
+    assert_eq!(mat, None);
+    let peak = PEAK.load(Ordering::Relaxed) - before;
+    assert!(peak < 1 << 20, "searching took {} bytes", peak);
+}
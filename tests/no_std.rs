@@ -0,0 +1,48 @@
+//! Searching without `std`, where the window only holds the last 256 bytes read.
+//! Run by `external::cargo_test_no_std`, the regexes are compiled by the macro instead.
+#![cfg(not(feature = "std"))]
+
+use hotsauce::regex;
+
+#[test]
+fn short_matches() {
+    let regex = regex!("[0-9]+");
+    let matches = regex.matches("ab 12 c 345".bytes()).collect::<Vec<_>>();
+    assert_eq!(matches, [3..5, 8..11]);
+}
+
+#[test]
+fn long_backtrack() {
+    // The search looks past the end of the match for longer ones, more than the window holds.
+    let hay = || "a".bytes().chain([b'b'; 300]);
+    let matches = regex!("a(b*c)?|b").matches(hay()).collect::<Vec<_>>();
+    let expected = (0..301).map(|i| i..i + 1).collect::<Vec<_>>();
+    assert_eq!(matches, expected);
+
+    let hay = || "x".bytes().chain([b'b'; 600]);
+    let matches = regex!("xb*y|b").matches(hay()).collect::<Vec<_>>();
+    let expected = (1..601).map(|i| i..i + 1).collect::<Vec<_>>();
+    assert_eq!(matches, expected);
+}
+
+#[test]
+fn long_match() {
+    // The start of the second match was dropped, so the search stops there.
+    let hay = "a z a".bytes().chain([b'b'; 300]).chain("z a z".bytes());
+    let mut matches = regex!("a[^z]*z").matches(hay);
+    assert_eq!(matches.next(), Some(0..3));
+    assert!(!matches.is_truncated());
+    assert_eq!(matches.next(), None);
+    assert!(matches.is_truncated());
+    assert_eq!(matches.next(), None);
+}
+
+#[test]
+fn long_match_without_dropped_start() {
+    // Only bytes no match starts in were dropped.
+    let hay = [b'z'; 300].into_iter().chain("a z".bytes());
+    let mut matches = regex!("a[^z]*z").matches(hay);
+    assert_eq!(matches.next(), Some(300..303));
+    assert_eq!(matches.next(), None);
+    assert!(!matches.is_truncated());
+}